@@ -0,0 +1,129 @@
+//! Diagnostics reported while scanning, such as lexical errors.
+
+use crate::token::*;
+use std::error::Error;
+use std::fmt;
+
+/// `*/` is expected, but the end of file is found in a block comment.
+pub const UNTERMINATED_COMMENT: &str = "E0001";
+/// `'` is expected, but the end of line is found in a char literal.
+pub const UNTERMINATED_CHAR: &str = "E0002";
+/// `"` is expected, but the end of line is found in a string literal.
+pub const UNTERMINATED_STRING: &str = "E0003";
+/// Char literal does not contain exactly one character.
+pub const INVALID_CHAR_LITERAL: &str = "E0004";
+/// Number literal is not well-formed, such as `0x` or `1.2.3`.
+pub const MALFORMED_NUMBER: &str = "E0005";
+/// Number literal can not be represented by its type.
+pub const NUMBER_OUT_OF_RANGE: &str = "E0006";
+/// Source file contains bytes which are not valid in its encoding.
+pub const INVALID_BYTE_SEQUENCE: &str = "E0007";
+/// Escape sequence in char or string literal is invalid, such as `\q` or `\u12`.
+pub const INVALID_ESCAPE: &str = "E0008";
+/// Character can not start any token, such as `@` or `#`.
+pub const UNKNOWN_CHARACTER: &str = "E0009";
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// The severity of diagnostic
+pub enum Severity {
+    /// the source code is invalid
+    ERROR,
+    /// the source code is valid, but suspicious
+    WARNING,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::ERROR => write!(f, "error"),
+            Severity::WARNING => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Diagnostic reported while processing the source code
+pub struct Diagnostic {
+    severity_: Severity,
+    code_: &'static str,
+    message_: String,
+    location_: TokenLocation,
+}
+
+impl Diagnostic {
+    /// New one diagnostic which covers the source code of `location`.
+    pub fn new(severity: Severity, code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic {
+            severity_: severity,
+            code_: code,
+            message_: message,
+            location_: location,
+        }
+    }
+
+    /// New one `Severity::ERROR` diagnostic.
+    pub fn error(code: &'static str, message: String, location: TokenLocation) -> Self {
+        Diagnostic::new(Severity::ERROR, code, message, location)
+    }
+
+    /// Get the severity, such as `Severity::ERROR`.
+    pub fn get_severity(&self) -> Severity {
+        self.severity_
+    }
+
+    /// Get the stable error code, such as `E0001`.
+    pub fn get_code(&self) -> &'static str {
+        self.code_
+    }
+
+    /// Get the message which explains the diagnostic.
+    pub fn get_message(&self) -> &str {
+        &self.message_
+    }
+
+    /// Get the location which the diagnostic covers.
+    pub fn get_location(&self) -> &TokenLocation {
+        &self.location_
+    }
+
+    /// Check if the severity is `Severity::ERROR`.
+    pub fn is_error(&self) -> bool {
+        self.severity_ == Severity::ERROR
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}[{}]: {}", self.location_, self.severity_, self.code_, self.message_)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Lexical error yielded by the scanner instead of a token
+pub struct LexError {
+    diagnostic_: Diagnostic,
+}
+
+impl LexError {
+    /// New lexical error which is reported by `diagnostic`.
+    pub fn new(diagnostic: Diagnostic) -> Self {
+        LexError {
+            diagnostic_: diagnostic,
+        }
+    }
+
+    /// Get the diagnostic which reports the error.
+    pub fn get_diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic_
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.diagnostic_)
+    }
+}
+
+impl Error for LexError {}
@@ -1,24 +1,85 @@
+//! Dictionary of keywords, types, operators and delimiters for every dialect.
+
 use crate::token::*;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+/// The dialect of language, which decides the reserved words, types and operators
+pub enum Dialect {
+    /// the MiniJava of Appel, with only `&&`, `<`, `+`, `-`, `*` and `!` operators
+    MINIJAVA,
+    /// MiniJava extended with `for`, `char`, `double`, `long`, `float` and the operators of Java except `->`
+    #[default]
+    MJAVA,
+    /// the subset of Java, which adds the other keywords of Java, such as `null` and `break`, and `->`, `::`, `...` and `@`,
+    /// while `main`, `length` and `String` are ordinary identifiers
+    JAVA_SUBSET,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dialect::MINIJAVA => write!(f, "minijava"),
+            Dialect::MJAVA => write!(f, "mjava"),
+            Dialect::JAVA_SUBSET => write!(f, "java-subset"),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    /// Parse the dialect name, such as `minijava` or `java-subset`, case and `-` / `_` are ignored.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let normalized: String = name.chars().filter(|ch| *ch != '-' && *ch != '_').collect();
+
+        match normalized.to_ascii_lowercase().as_str() {
+            "minijava" => Ok(Dialect::MINIJAVA),
+            "mjava" => Ok(Dialect::MJAVA),
+            "javasubset" => Ok(Dialect::JAVA_SUBSET),
+            _ => Err(format!("unknown dialect \"{}\", expected minijava, mjava or java-subset", name)),
+        }
+    }
+}
+
+/// `(TokenValue, TokenType, precedence)` of the token in dictionary
+pub type TokenInfo = (TokenValue, TokenType, i32);
 
+#[derive(Clone)]
 /// Dictionary of token
 pub struct Dictionary {
-    dictionary_: HashMap<String, (TokenValue, TokenType, i32)>,
+    dictionary_: HashMap<String, TokenInfo>,
+    max_symbol_length_: usize,
 }
 
 impl Dictionary {
-    /// Get the default dictionary, which has already inserted reserved word.
+    /// Get the default dictionary, which has already inserted reserved word of `Dialect::MJAVA`.
     pub fn get_dictionary() -> Dictionary {
+        Dictionary::for_dialect(Dialect::MJAVA)
+    }
+
+    /// Get the dictionary which has inserted the reserved words, types and operators of `dialect`.
+    pub fn for_dialect(dialect: Dialect) -> Dictionary {
         let mut dic = Dictionary {
             dictionary_: HashMap::new(),
+            max_symbol_length_: 0,
         };
 
+        // the precedences follow Java, from 0 for assignment to 60 for `.`, so `<` binds tighter than `&&`
+        // MiniJava
         dic.dictionary_.insert("=".to_string(), (TokenValue::ASSIGN, TokenType::OPERATOR, 0));
-        dic.dictionary_.insert("<".to_string(), (TokenValue::LT, TokenType::OPERATOR, 2));
+        dic.dictionary_.insert("<".to_string(), (TokenValue::LT, TokenType::OPERATOR, 8));
         dic.dictionary_.insert("+".to_string(), (TokenValue::ADD, TokenType::OPERATOR, 10));
         dic.dictionary_.insert("-".to_string(), (TokenValue::SUB, TokenType::OPERATOR, 10));
         dic.dictionary_.insert("*".to_string(), (TokenValue::MULTI, TokenType::OPERATOR, 20));
-        dic.dictionary_.insert("&&".to_string(), (TokenValue::AND, TokenType::OPERATOR, 20));
+        dic.dictionary_.insert("&&".to_string(), (TokenValue::AND, TokenType::OPERATOR, 3));
         dic.dictionary_.insert("!".to_string(), (TokenValue::NOT, TokenType::OPERATOR, 40));
         dic.dictionary_.insert(".".to_string(), (TokenValue::DOT, TokenType::OPERATOR, 60));
         dic.dictionary_.insert("(".to_string(), (TokenValue::LPAREN, TokenType::DELIMITER, -1));
@@ -33,32 +94,282 @@ impl Dictionary {
         dic.dictionary_.insert("public".to_string(), (TokenValue::PUBLIC, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("static".to_string(), (TokenValue::STATIC, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("void".to_string(), (TokenValue::VOID, TokenType::KEYWORD, -1));
-        dic.dictionary_.insert("main".to_string(), (TokenValue::MAIN, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("extends".to_string(), (TokenValue::EXTENDS, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("return".to_string(), (TokenValue::RETURN, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("if".to_string(), (TokenValue::IF, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("else".to_string(), (TokenValue::ELSE, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("while".to_string(), (TokenValue::WHILE, TokenType::KEYWORD, -1));
-        dic.dictionary_.insert("for".to_string(), (TokenValue::FOR, TokenType::KEYWORD, -1));
-        dic.dictionary_.insert("System.out.println".to_string(), (TokenValue::PRINT, TokenType::KEYWORD, -1));
-        dic.dictionary_.insert("length".to_string(), (TokenValue::LENGTH, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("this".to_string(), (TokenValue::THIS, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("new".to_string(), (TokenValue::NEW, TokenType::KEYWORD, -1));
         dic.dictionary_.insert("true".to_string(), (TokenValue::TRUE, TokenType::BOOLEAN_LITERAL, -1));
         dic.dictionary_.insert("false".to_string(), (TokenValue::FALSE, TokenType::BOOLEAN_LITERAL, -1));
         dic.dictionary_.insert("int".to_string(), (TokenValue::INT, TokenType::TYPE, -1));
-        dic.dictionary_.insert("char".to_string(), (TokenValue::CHAR, TokenType::TYPE, -1));
-        dic.dictionary_.insert("String".to_string(), (TokenValue::STRING, TokenType::TYPE, -1));
         dic.dictionary_.insert("boolean".to_string(), (TokenValue::BOOL, TokenType::TYPE, -1));
+
+        if dialect != Dialect::JAVA_SUBSET {
+            // they are ordinary identifiers in Java
+            dic.dictionary_.insert("main".to_string(), (TokenValue::MAIN, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("length".to_string(), (TokenValue::LENGTH, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("String".to_string(), (TokenValue::STRING, TokenType::TYPE, -1));
+        }
+
+        if dialect != Dialect::MINIJAVA {
+            dic.dictionary_.insert("+=".to_string(), (TokenValue::ADD_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("-=".to_string(), (TokenValue::SUB_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("*=".to_string(), (TokenValue::MULTI_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("/=".to_string(), (TokenValue::DIV_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("%=".to_string(), (TokenValue::MOD_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("&=".to_string(), (TokenValue::BIT_AND_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("|=".to_string(), (TokenValue::BIT_OR_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("^=".to_string(), (TokenValue::BIT_XOR_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("<<=".to_string(), (TokenValue::SHL_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert(">>=".to_string(), (TokenValue::SHR_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert(">>>=".to_string(), (TokenValue::USHR_ASSIGN, TokenType::OPERATOR, 0));
+            dic.dictionary_.insert("?".to_string(), (TokenValue::QUESTION, TokenType::OPERATOR, 1));
+            dic.dictionary_.insert(":".to_string(), (TokenValue::COLON, TokenType::OPERATOR, 1));
+            dic.dictionary_.insert("||".to_string(), (TokenValue::OR, TokenType::OPERATOR, 2));
+            dic.dictionary_.insert("|".to_string(), (TokenValue::BIT_OR, TokenType::OPERATOR, 4));
+            dic.dictionary_.insert("^".to_string(), (TokenValue::BIT_XOR, TokenType::OPERATOR, 5));
+            dic.dictionary_.insert("&".to_string(), (TokenValue::BIT_AND, TokenType::OPERATOR, 6));
+            dic.dictionary_.insert("==".to_string(), (TokenValue::EQ, TokenType::OPERATOR, 7));
+            dic.dictionary_.insert("!=".to_string(), (TokenValue::NE, TokenType::OPERATOR, 7));
+            dic.dictionary_.insert(">".to_string(), (TokenValue::GT, TokenType::OPERATOR, 8));
+            dic.dictionary_.insert("<=".to_string(), (TokenValue::LE, TokenType::OPERATOR, 8));
+            dic.dictionary_.insert(">=".to_string(), (TokenValue::GE, TokenType::OPERATOR, 8));
+            dic.dictionary_.insert("<<".to_string(), (TokenValue::SHL, TokenType::OPERATOR, 9));
+            dic.dictionary_.insert(">>".to_string(), (TokenValue::SHR, TokenType::OPERATOR, 9));
+            dic.dictionary_.insert(">>>".to_string(), (TokenValue::USHR, TokenType::OPERATOR, 9));
+            dic.dictionary_.insert("/".to_string(), (TokenValue::DIV, TokenType::OPERATOR, 20));
+            dic.dictionary_.insert("%".to_string(), (TokenValue::MOD, TokenType::OPERATOR, 20));
+            dic.dictionary_.insert("~".to_string(), (TokenValue::BIT_NOT, TokenType::OPERATOR, 40));
+            dic.dictionary_.insert("++".to_string(), (TokenValue::INC, TokenType::OPERATOR, 40));
+            dic.dictionary_.insert("--".to_string(), (TokenValue::DEC, TokenType::OPERATOR, 40));
+            dic.dictionary_.insert("for".to_string(), (TokenValue::FOR, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("char".to_string(), (TokenValue::CHAR, TokenType::TYPE, -1));
+            dic.dictionary_.insert("double".to_string(), (TokenValue::DOUBLE, TokenType::TYPE, -1));
+            // the types of `10L` and `1.5f`
+            dic.dictionary_.insert("long".to_string(), (TokenValue::LONG, TokenType::TYPE, -1));
+            dic.dictionary_.insert("float".to_string(), (TokenValue::FLOAT, TokenType::TYPE, -1));
+        }
+
+        if dialect == Dialect::JAVA_SUBSET {
+            dic.dictionary_.insert("private".to_string(), (TokenValue::PRIVATE, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("protected".to_string(), (TokenValue::PROTECTED, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("final".to_string(), (TokenValue::FINAL, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("abstract".to_string(), (TokenValue::ABSTRACT, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("interface".to_string(), (TokenValue::INTERFACE, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("implements".to_string(), (TokenValue::IMPLEMENTS, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("import".to_string(), (TokenValue::IMPORT, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("package".to_string(), (TokenValue::PACKAGE, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("super".to_string(), (TokenValue::SUPER, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("null".to_string(), (TokenValue::NULL, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("instanceof".to_string(), (TokenValue::INSTANCEOF, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("break".to_string(), (TokenValue::BREAK, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("continue".to_string(), (TokenValue::CONTINUE, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("do".to_string(), (TokenValue::DO, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("switch".to_string(), (TokenValue::SWITCH, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("case".to_string(), (TokenValue::CASE, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("default".to_string(), (TokenValue::DEFAULT, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("try".to_string(), (TokenValue::TRY, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("catch".to_string(), (TokenValue::CATCH, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("finally".to_string(), (TokenValue::FINALLY, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("throw".to_string(), (TokenValue::THROW, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("throws".to_string(), (TokenValue::THROWS, TokenType::KEYWORD, -1));
+            dic.dictionary_.insert("byte".to_string(), (TokenValue::BYTE, TokenType::TYPE, -1));
+            dic.dictionary_.insert("short".to_string(), (TokenValue::SHORT, TokenType::TYPE, -1));
+            // MJava has no lambdas, method references, varargs or annotations, so they are unknown there
+            dic.dictionary_.insert("->".to_string(), (TokenValue::ARROW, TokenType::OPERATOR, -1));
+            dic.dictionary_.insert("::".to_string(), (TokenValue::DOUBLE_COLON, TokenType::DELIMITER, -1));
+            dic.dictionary_.insert("...".to_string(), (TokenValue::ELLIPSIS, TokenType::DELIMITER, -1));
+            dic.dictionary_.insert("@".to_string(), (TokenValue::AT, TokenType::DELIMITER, -1));
+        }
+
+        dic.max_symbol_length_ = dic.dictionary_.keys().filter(|name| Dictionary::is_symbol(name))
+            .map(|name| name.chars().count()).max().unwrap_or(0);
         dic
     }
 
-    #[allow(dead_code)]
+    /// Check if name is made up of symbols, such as `>>>=`, rather than a word.
+    fn is_symbol(name: &str) -> bool {
+        match name.chars().next() {
+            Some(ch) => !ch.is_alphanumeric() && ch != '_',
+            None => false,
+        }
+    }
+
+    /// Check if name is a word, such as `until`, which is scanned like an identifier.
+    fn is_word(name: &str) -> bool {
+        let mut chars = name.chars();
+
+        match chars.next() {
+            Some(ch) => ch.is_ascii_alphabetic() && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+            None => false,
+        }
+    }
+
+    /// Check if name is made up of the symbols which can be scanned as operator or delimiter,
+    /// that is punctuation except quotes, `_` and the start of comment anywhere in it.
+    fn is_operation(name: &str) -> bool {
+        !name.is_empty() && !name.contains("//") && !name.contains("/*")
+            && name.chars().all(|ch| ch.is_ascii_punctuation() && ch != '\'' && ch != '\"' && ch != '_')
+    }
+
+    /// Get the types which `token_value` can belong to, such as `KEYWORD` for `CLASS`.
+    ///
+    /// `UNRESERVED` can belong to every type except `BOOLEAN_LITERAL`, which is only `TRUE` or `FALSE`.
+    #[allow(deprecated)]
+    fn get_value_types(token_value: TokenValue) -> &'static [TokenType] {
+        match token_value {
+            TokenValue::CLASS | TokenValue::PUBLIC | TokenValue::STATIC | TokenValue::VOID | TokenValue::MAIN
+                | TokenValue::EXTENDS | TokenValue::RETURN | TokenValue::IF | TokenValue::ELSE | TokenValue::WHILE
+                | TokenValue::FOR | TokenValue::PRINT | TokenValue::LENGTH | TokenValue::THIS | TokenValue::NEW
+                | TokenValue::PRIVATE | TokenValue::PROTECTED | TokenValue::FINAL | TokenValue::ABSTRACT
+                | TokenValue::INTERFACE | TokenValue::IMPLEMENTS | TokenValue::IMPORT | TokenValue::PACKAGE
+                | TokenValue::SUPER | TokenValue::NULL | TokenValue::INSTANCEOF | TokenValue::BREAK
+                | TokenValue::CONTINUE | TokenValue::DO | TokenValue::SWITCH | TokenValue::CASE | TokenValue::DEFAULT
+                | TokenValue::TRY | TokenValue::CATCH | TokenValue::FINALLY | TokenValue::THROW | TokenValue::THROWS => &[TokenType::KEYWORD],
+            TokenValue::DOUBLE | TokenValue::INT | TokenValue::CHAR | TokenValue::STRING | TokenValue::BOOL
+                | TokenValue::BYTE | TokenValue::SHORT | TokenValue::LONG | TokenValue::FLOAT => &[TokenType::TYPE],
+            TokenValue::TRUE | TokenValue::FALSE => &[TokenType::BOOLEAN_LITERAL],
+            TokenValue::LPAREN | TokenValue::RPAREN | TokenValue::LBRACK | TokenValue::RBRACK | TokenValue::LBRACE
+                | TokenValue::RBRACE | TokenValue::COMMA | TokenValue::SEMICOLON | TokenValue::DOUBLE_COLON
+                | TokenValue::ELLIPSIS | TokenValue::AT | TokenValue::ASSIGN | TokenValue::ADD_ASSIGN
+                | TokenValue::SUB_ASSIGN | TokenValue::MULTI_ASSIGN | TokenValue::DIV_ASSIGN | TokenValue::MOD_ASSIGN
+                | TokenValue::BIT_AND_ASSIGN | TokenValue::BIT_OR_ASSIGN | TokenValue::BIT_XOR_ASSIGN
+                | TokenValue::SHL_ASSIGN | TokenValue::SHR_ASSIGN | TokenValue::USHR_ASSIGN | TokenValue::QUESTION
+                | TokenValue::COLON | TokenValue::ARROW | TokenValue::OR | TokenValue::AND | TokenValue::BIT_OR
+                | TokenValue::BIT_XOR | TokenValue::BIT_AND | TokenValue::EQ | TokenValue::NE | TokenValue::LT
+                | TokenValue::GT | TokenValue::LE | TokenValue::GE | TokenValue::SHL | TokenValue::SHR
+                | TokenValue::USHR | TokenValue::ADD | TokenValue::SUB | TokenValue::MULTI | TokenValue::DIV
+                | TokenValue::MOD | TokenValue::INC | TokenValue::DEC | TokenValue::BIT_NOT | TokenValue::DOT
+                | TokenValue::NOT => &[TokenType::OPERATOR, TokenType::DELIMITER],
+            TokenValue::UNRESERVED => &[TokenType::KEYWORD, TokenType::TYPE, TokenType::OPERATOR, TokenType::DELIMITER],
+        }
+    }
+
     /// Add token to dictionary.
-    pub fn add_token(&mut self, name: String, info: (TokenValue, TokenType, i32)) {
+    pub fn add_token(&mut self, name: String, info: TokenInfo) {
+        if Dictionary::is_symbol(&name) {
+            self.max_symbol_length_ = self.max_symbol_length_.max(name.chars().count());
+        }
+
         self.dictionary_.insert(name, info);
     }
 
+    /// Load the tokens defined in JSON spec into dictionary, such as
+    ///
+    /// ```json
+    /// {
+    ///     "tokens": [
+    ///         { "lexeme": "until", "type": "KEYWORD", "value": "WHILE" },
+    ///         { "lexeme": "**", "type": "OPERATOR", "precedence": 30 }
+    ///     ]
+    /// }
+    /// ```
+    ///
+    /// `type` and `value` are the names of `TokenType` and `TokenValue`, `value` is `UNRESERVED` and `precedence` is `-1`
+    /// if omitted. `value` should be one of `type`, such as `TRUE` or `FALSE` for `BOOLEAN_LITERAL`, and only `OPERATOR`
+    /// can have `precedence`. A lexeme which is already in dictionary can only be redefined with `"replace": true`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the messages of all malformed and conflicting entries, and nothing is loaded then.
+    pub fn load_spec(&mut self, spec: &str) -> Result<(), Vec<String>> {
+        let spec: Value = serde_json::from_str(spec).map_err(|err| vec![format!("invalid JSON, {}", err)])?;
+
+        let entries = match spec.as_object() {
+            Some(fields) if fields.keys().all(|key| key == "tokens") => fields.get("tokens").and_then(Value::as_array),
+            _ => None,
+        };
+
+        let entries = entries.ok_or_else(|| vec!["spec should be an object with only the \"tokens\" array".to_string()])?;
+
+        let mut errors = Vec::new();
+        let mut tokens = Vec::new();
+        let mut names = HashSet::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            match Dictionary::parse_spec_entry(entry) {
+                Err(err) => errors.push(format!("tokens[{}]: {}", index, err)),
+                Ok((name, info, replace)) => {
+                    if !names.insert(name.to_owned()) {
+                        errors.push(format!("tokens[{}]: \"{}\" is defined more than once.", index, name));
+                    } else if self.have_token(&name) && !replace {
+                        errors.push(format!("tokens[{}]: \"{}\" conflicts with the one in dictionary, set \"replace\" to redefine it.",
+                                index, name));
+                    } else {
+                        tokens.push((name, info));
+                    }
+                },
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        for (name, info) in tokens {
+            self.add_token(name, info);
+        }
+
+        Ok(())
+    }
+
+    /// Parse one entry of spec, and return the lexeme, its `(TokenValue, TokenType, precedence)` and whether to replace.
+    fn parse_spec_entry(entry: &Value) -> Result<(String, TokenInfo, bool), String> {
+        const FIELDS: [&str; 5] = ["lexeme", "type", "value", "precedence", "replace"];
+
+        let fields = entry.as_object().ok_or("entry should be an object.")?;
+
+        if let Some(key) = fields.keys().find(|key| !FIELDS.contains(&key.as_str())) {
+            return Err(format!("unknown field \"{}\".", key));
+        }
+
+        let name = fields.get("lexeme").and_then(Value::as_str).ok_or("\"lexeme\" should be a string.")?;
+        let token_type: TokenType = fields.get("type").and_then(Value::as_str).ok_or("\"type\" should be a string.")?.parse()?;
+
+        let token_value: TokenValue = match fields.get("value") {
+            None => TokenValue::UNRESERVED,
+            Some(value) => value.as_str().ok_or("\"value\" should be a string.")?.parse()?,
+        };
+
+        let precedence = match fields.get("precedence") {
+            None => -1,
+            Some(precedence) => precedence.as_i64().and_then(|precedence| i32::try_from(precedence).ok())
+                .ok_or("\"precedence\" should be an integer.")?,
+        };
+
+        let replace = match fields.get("replace") {
+            None => false,
+            Some(replace) => replace.as_bool().ok_or("\"replace\" should be a boolean.")?,
+        };
+
+        if Dictionary::is_word(name) {
+            if !matches!(token_type, TokenType::KEYWORD | TokenType::TYPE | TokenType::BOOLEAN_LITERAL | TokenType::OPERATOR) {
+                return Err(format!("word \"{}\" can only be KEYWORD, TYPE, BOOLEAN_LITERAL or OPERATOR.", name));
+            }
+        } else if Dictionary::is_operation(name) {
+            if !matches!(token_type, TokenType::OPERATOR | TokenType::DELIMITER) {
+                return Err(format!("symbol \"{}\" can only be OPERATOR or DELIMITER.", name));
+            }
+        } else {
+            return Err(format!("lexeme \"{}\" should be either a word such as `until`, or symbols such as `**`.", name));
+        }
+
+        if fields.contains_key("precedence") && token_type != TokenType::OPERATOR {
+            return Err(format!("\"precedence\" is only for OPERATOR, not {:?}.", token_type));
+        }
+
+        if !Dictionary::get_value_types(token_value).contains(&token_type) {
+            return Err(match token_type {
+                TokenType::BOOLEAN_LITERAL => "BOOLEAN_LITERAL should have the value TRUE or FALSE.".to_string(),
+                _ => format!("value {:?} is not {:?}.", token_value, token_type),
+            });
+        }
+
+        Ok((name.to_string(), (token_value, token_type, precedence), replace))
+    }
+
     /// Find out if name exists,and return `(TokenValue, TokenType, precedence)`.
     ///
     /// if so, return the corresponding tuple,
@@ -81,4 +392,115 @@ impl Dictionary {
     pub fn have_token(&self, name: &String) -> bool {
         self.dictionary_.contains_key(name)
     }
+
+    /// Get the length of the longest operator or delimiter, which is how far the scanner looks ahead.
+    pub fn get_max_symbol_length(&self) -> usize {
+        self.max_symbol_length_
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(dic: &Dictionary, name: &str) -> TokenInfo {
+        dic.lookup(&name.to_string())
+    }
+
+    #[test]
+    fn operator_precedences_follow_java() {
+        let dic = Dictionary::for_dialect(Dialect::MJAVA);
+        let table = [
+            ("=", 0), ("+=", 0), ("-=", 0), ("*=", 0), ("/=", 0), ("%=", 0), ("&=", 0), ("|=", 0), ("^=", 0),
+            ("<<=", 0), (">>=", 0), (">>>=", 0), ("?", 1), (":", 1), ("||", 2), ("&&", 3), ("|", 4), ("^", 5), ("&", 6),
+            ("==", 7), ("!=", 7), ("<", 8), (">", 8), ("<=", 8), (">=", 8), ("<<", 9), (">>", 9), (">>>", 9),
+            ("+", 10), ("-", 10), ("*", 20), ("/", 20), ("%", 20), ("!", 40), ("~", 40), ("++", 40), ("--", 40), (".", 60),
+        ];
+
+        for (name, precedence) in table {
+            let (_, token_type, symbol_precedence) = lookup(&dic, name);
+            assert_eq!((token_type, symbol_precedence), (TokenType::OPERATOR, precedence), "{}", name);
+        }
+    }
+
+    #[test]
+    fn minijava_has_only_the_operators_of_appel() {
+        let dic = Dictionary::for_dialect(Dialect::MINIJAVA);
+
+        for name in ["=", "<", "+", "-", "*", "&&", "!", "."] {
+            assert!(dic.have_token(&name.to_string()), "{}", name);
+        }
+
+        for name in ["||", "/", "==", "++", "+=", ">"] {
+            assert!(!dic.have_token(&name.to_string()), "{}", name);
+        }
+
+        assert_eq!(dic.get_max_symbol_length(), 2);
+    }
+
+    #[test]
+    fn java_separators_are_only_in_java_subset() {
+        let mjava = Dictionary::for_dialect(Dialect::MJAVA);
+        let java = Dictionary::for_dialect(Dialect::JAVA_SUBSET);
+
+        for name in ["->", "::", "...", "@"] {
+            assert!(!mjava.have_token(&name.to_string()), "{}", name);
+            assert!(java.have_token(&name.to_string()), "{}", name);
+        }
+    }
+
+    fn load_errors(spec: &str) -> Vec<String> {
+        let mut dic = Dictionary::get_dictionary();
+        let errors = dic.load_spec(spec).err().unwrap_or_default();
+        // nothing is loaded if there is any error
+        assert!(errors.is_empty() || !dic.have_token(&"until".to_string()));
+        errors
+    }
+
+    #[test]
+    fn spec_is_loaded() {
+        let mut dic = Dictionary::get_dictionary();
+        let spec = r#"{ "tokens": [
+            { "lexeme": "until", "type": "KEYWORD", "value": "WHILE" },
+            { "lexeme": "**", "type": "OPERATOR", "precedence": 30 },
+            { "lexeme": "yes", "type": "BOOLEAN_LITERAL", "value": "TRUE" },
+            { "lexeme": "<", "type": "OPERATOR", "value": "LT", "precedence": 9, "replace": true }
+        ] }"#;
+
+        assert_eq!(dic.load_spec(spec), Ok(()));
+        assert_eq!(lookup(&dic, "until"), (TokenValue::WHILE, TokenType::KEYWORD, -1));
+        assert_eq!(lookup(&dic, "**"), (TokenValue::UNRESERVED, TokenType::OPERATOR, 30));
+        assert_eq!(lookup(&dic, "yes"), (TokenValue::TRUE, TokenType::BOOLEAN_LITERAL, -1));
+        assert_eq!(lookup(&dic, "<"), (TokenValue::LT, TokenType::OPERATOR, 9));
+    }
+
+    #[test]
+    fn spec_entries_are_validated() {
+        let cases = [
+            (r#"{ "lexeme": "until", "type": "KEYWORD" }, { "lexeme": "until", "type": "KEYWORD" }"#, "defined more than once"),
+            (r#"{ "lexeme": "while", "type": "KEYWORD", "value": "WHILE" }"#, "set \"replace\" to redefine it"),
+            (r#"{ "lexeme": "$", "type": "DELIMITER", "value": "CLASS" }"#, "value CLASS is not DELIMITER"),
+            (r#"{ "lexeme": "num", "type": "TYPE", "value": "ADD" }"#, "value ADD is not TYPE"),
+            (r#"{ "lexeme": "yes", "type": "BOOLEAN_LITERAL" }"#, "should have the value TRUE or FALSE"),
+            (r#"{ "lexeme": "$", "type": "DELIMITER", "precedence": 3 }"#, "\"precedence\" is only for OPERATOR"),
+            (r#"{ "lexeme": "+//", "type": "OPERATOR" }"#, "should be either a word"),
+            (r#"{ "lexeme": "+/*", "type": "OPERATOR" }"#, "should be either a word"),
+            (r#"{ "lexeme": "until", "type": "IDENTIFIER" }"#, "can only be KEYWORD"),
+            (r#"{ "lexeme": "until", "type": "KEYWORD", "kind": 1 }"#, "unknown field \"kind\""),
+        ];
+
+        for (entries, message) in cases {
+            let spec = format!(r#"{{ "tokens": [{}] }}"#, entries);
+            let errors = load_errors(&spec);
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+            assert!(errors[0].contains(message), "{} does not contain {}", errors[0], message);
+        }
+    }
+
+    #[test]
+    fn spec_should_be_an_object_of_tokens() {
+        assert_eq!(load_errors(r#"[]"#), ["spec should be an object with only the \"tokens\" array"]);
+        assert_eq!(load_errors(r#"{ "tokens": [], "extra": 1 }"#), ["spec should be an object with only the \"tokens\" array"]);
+        assert!(load_errors("{").iter().all(|err| err.starts_with("invalid JSON")));
+    }
 }
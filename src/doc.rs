@@ -0,0 +1,285 @@
+//! Attach doc comments to declarations and generate the API reference.
+
+use crate::token::*;
+use std::fmt;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// The kind of declaration which doc comment is attached to
+pub enum DeclarationKind {
+    /// class declaration
+    CLASS,
+    /// field declaration
+    FIELD,
+    /// method declaration
+    METHOD,
+}
+
+impl fmt::Display for DeclarationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeclarationKind::CLASS => write!(f, "class"),
+            DeclarationKind::FIELD => write!(f, "field"),
+            DeclarationKind::METHOD => write!(f, "method"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Doc comment attached to the class, field or method declared after it
+pub struct DocItem {
+    kind_: DeclarationKind,
+    name_: String,
+    class_name_: Option<String>,
+    text_: String,
+    location_: TokenLocation,
+}
+
+impl DocItem {
+    /// Get the kind of declaration.
+    pub fn get_kind(&self) -> DeclarationKind {
+        self.kind_
+    }
+
+    /// Get the declared name.
+    pub fn get_name(&self) -> &str {
+        &self.name_
+    }
+
+    /// Get the name of class which the field or method belongs to, `None` for the class itself.
+    pub fn get_class_name(&self) -> Option<&str> {
+        self.class_name_.as_deref()
+    }
+
+    /// Get the text of doc comment.
+    pub fn get_text(&self) -> &str {
+        &self.text_
+    }
+
+    /// Get the location of the declared name.
+    pub fn get_location(&self) -> &TokenLocation {
+        &self.location_
+    }
+
+    /// Get the name qualified by class, such as `Fac.ComputeFac`.
+    pub fn get_qualified_name(&self) -> String {
+        match &self.class_name_ {
+            Some(class_name) => format!("{}.{}", class_name, self.name_),
+            None => self.name_.to_owned(),
+        }
+    }
+}
+
+/// Attach every doc comment to the class, field or method declared right after it.
+///
+/// Doc comments which are not followed by a declaration, such as those before statements, are ignored.
+/// If several doc comments come in a row, only the last one is attached.
+pub fn attach_doc_comments(tokens: &[Token]) -> Vec<DocItem> {
+    let mut items = Vec::new();
+    // the name of class and the depth of braces of its body
+    let mut classes: Vec<(String, usize)> = Vec::new();
+    let mut class_name = None;
+    let mut depth = 0;
+    let mut doc_comment: Option<&Token> = None;
+
+    for (index, token) in tokens.iter().enumerate() {
+        if let TokenType::DOC_COMMENT = token.get_token_type() {
+            doc_comment = Some(token);
+            continue;
+        }
+
+        if let Some(comment) = doc_comment.take() {
+            let owner = classes.last().filter(|(_, class_depth)| *class_depth == depth).map(|(name, _)| name.to_owned());
+
+            match match_declaration(&tokens[index..]) {
+                Some((DeclarationKind::CLASS, name)) if depth == 0 => {
+                    items.push(new_doc_item(DeclarationKind::CLASS, name, None, comment));
+                },
+                Some((kind, name)) if kind != DeclarationKind::CLASS && owner.is_some() => {
+                    items.push(new_doc_item(kind, name, owner, comment));
+                },
+                _ => {},
+            }
+        }
+
+        match token.get_token_value() {
+            TokenValue::CLASS => {
+                class_name = tokens.get(index + 1).filter(|name| is_identifier(name)).map(|name| name.get_name().to_string());
+            },
+            TokenValue::LBRACE => {
+                depth += 1;
+
+                if let Some(name) = class_name.take() {
+                    classes.push((name, depth));
+                }
+            },
+            TokenValue::RBRACE => {
+                if classes.last().is_some_and(|(_, class_depth)| *class_depth == depth) {
+                    classes.pop();
+                }
+
+                depth = depth.saturating_sub(1);
+            },
+            _ => {},
+        }
+    }
+
+    items
+}
+
+fn new_doc_item(kind: DeclarationKind, name: &Token, class_name: Option<String>, comment: &Token) -> DocItem {
+    DocItem {
+        kind_: kind,
+        name_: name.get_name().to_string(),
+        class_name_: class_name,
+        text_: comment.get_str_value().to_string(),
+        location_: name.get_location().to_owned(),
+    }
+}
+
+fn is_identifier(token: &Token) -> bool {
+    matches!(token.get_token_type(), TokenType::IDENTIFIER)
+}
+
+/// Match the declaration at the start of tokens, and return its kind and the token of its name.
+///
+/// ```text
+/// [modifiers] class Name
+/// [modifiers] Type[[]] name (
+/// [modifiers] Type[[]] name ; | = | ,
+/// ```
+///
+/// where the modifiers are `public`, `private`, `protected`, `static`, `final` and `abstract`.
+fn match_declaration(tokens: &[Token]) -> Option<(DeclarationKind, &Token)> {
+    let mut tokens = tokens.iter().filter(|token| !matches!(token.get_token_type(), TokenType::DOC_COMMENT)).peekable();
+
+    while let Some(TokenValue::PUBLIC | TokenValue::PRIVATE | TokenValue::PROTECTED | TokenValue::STATIC | TokenValue::FINAL
+            | TokenValue::ABSTRACT) = tokens.peek().map(|token| token.get_token_value()) {
+        tokens.next();
+    }
+
+    let first = tokens.next()?;
+
+    if let TokenValue::CLASS = first.get_token_value() {
+        return tokens.next().filter(|name| is_identifier(name)).map(|name| (DeclarationKind::CLASS, name));
+    }
+
+    let is_void = matches!(first.get_token_value(), TokenValue::VOID);
+
+    if !(is_void || is_identifier(first) || matches!(first.get_token_type(), TokenType::TYPE)) {
+        return None;
+    }
+
+    while let Some(TokenValue::LBRACK) = tokens.peek().map(|token| token.get_token_value()) {
+        tokens.next();
+
+        if !matches!(tokens.next()?.get_token_value(), TokenValue::RBRACK) {
+            return None;
+        }
+    }
+
+    let name = tokens.next()?;
+
+    if !(is_identifier(name) || matches!(name.get_token_value(), TokenValue::MAIN)) {
+        return None;
+    }
+
+    match tokens.next()?.get_token_value() {
+        TokenValue::LPAREN => Some((DeclarationKind::METHOD, name)),
+        TokenValue::SEMICOLON | TokenValue::ASSIGN | TokenValue::COMMA if !is_void => Some((DeclarationKind::FIELD, name)),
+        _ => None,
+    }
+}
+
+/// Generate the API reference in Markdown from the doc items.
+pub fn to_markdown(items: &[DocItem]) -> String {
+    let mut markdown = String::from("# API Reference\n");
+
+    for item in items {
+        let heading = match item.get_kind() {
+            DeclarationKind::CLASS => "##",
+            _ => "###",
+        };
+
+        markdown.push_str(&format!("\n{} {} `{}`\n\n", heading, item.get_kind(), item.get_qualified_name()));
+        markdown.push_str(&format!("*{}:{}*\n", item.get_location().get_file_name(), item.get_location().get_line()));
+
+        if !item.get_text().is_empty() {
+            markdown.push_str(&format!("\n{}\n", item.get_text()));
+        }
+    }
+
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn attach(source: &str) -> Vec<(DeclarationKind, String, String)> {
+        let tokens: Vec<Token> = Scanner::from_str("<test>", source).filter_map(Result::ok).collect();
+
+        attach_doc_comments(&tokens).iter()
+            .map(|item| (item.get_kind(), item.get_qualified_name(), item.get_text().to_string()))
+            .collect()
+    }
+
+    fn item(kind: DeclarationKind, name: &str, text: &str) -> (DeclarationKind, String, String) {
+        (kind, name.to_string(), text.to_string())
+    }
+
+    #[test]
+    fn classes_fields_and_methods_are_documented() {
+        let source = "/** A */ class A {\n/** x */ int x;\n/** a */ int[] a;\n/** b */ B b = new B();\n\
+                      /** f */ public void f() {}\n/** g */ int[] g(int n) { return a; }\n}";
+
+        assert_eq!(attach(source), [
+            item(DeclarationKind::CLASS, "A", "A"),
+            item(DeclarationKind::FIELD, "A.x", "x"),
+            item(DeclarationKind::FIELD, "A.a", "a"),
+            item(DeclarationKind::FIELD, "A.b", "b"),
+            item(DeclarationKind::METHOD, "A.f", "f"),
+            item(DeclarationKind::METHOD, "A.g", "g"),
+        ]);
+    }
+
+    #[test]
+    fn main_method_is_documented() {
+        let source = "class Main { /** entry */ public static void main(String[] args) {} }";
+        assert_eq!(attach(source), [item(DeclarationKind::METHOD, "Main.main", "entry")]);
+    }
+
+    #[test]
+    fn nested_classes_own_their_members() {
+        let source = "class A { class B { /** y */ int y; } /** x */ int x; }";
+        assert_eq!(attach(source), [item(DeclarationKind::FIELD, "B.y", "y"), item(DeclarationKind::FIELD, "A.x", "x")]);
+
+        // only top-level classes are documented as classes
+        assert!(attach("class A { /** B */ class B {} }").is_empty());
+    }
+
+    #[test]
+    fn doc_comments_before_statements_are_ignored() {
+        let source = "class A { void f() { /** local */ int a; /** stmt */ a = 1; } /** x */ int x; }";
+        assert_eq!(attach(source), [item(DeclarationKind::FIELD, "A.x", "x")]);
+
+        assert!(attach("/** field outside class */ int x;").is_empty());
+        assert!(attach("class A { /** void field */ void x; }").is_empty());
+        assert!(attach("class A {} /** nothing after it */").is_empty());
+    }
+
+    #[test]
+    fn only_the_last_of_doc_comments_in_a_row_is_attached() {
+        let source = "class A { /** first */ /** second */ int x; }";
+        assert_eq!(attach(source), [item(DeclarationKind::FIELD, "A.x", "second")]);
+    }
+
+    #[test]
+    fn markdown_has_headings_and_locations() {
+        let tokens: Vec<Token> = Scanner::from_str("<test>", "/** A */\nclass A {\n/** f */\nvoid f() {} }").filter_map(Result::ok).collect();
+        let markdown = to_markdown(&attach_doc_comments(&tokens));
+        assert_eq!(markdown, "# API Reference\n\n## class `A`\n\n*<test>:2*\n\nA\n\n### method `A.f`\n\n*<test>:4*\n\nf\n");
+    }
+}
@@ -0,0 +1,210 @@
+//! Dump tokens as text, JSON or CSV.
+
+use crate::token::*;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+/// The format of token dump
+pub enum DumpFormat {
+    /// one `Token::to_string` per line
+    #[default]
+    TEXT,
+    /// an array of token objects
+    JSON,
+    /// a header line and one record per token
+    CSV,
+}
+
+impl fmt::Display for DumpFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DumpFormat::TEXT => write!(f, "text"),
+            DumpFormat::JSON => write!(f, "json"),
+            DumpFormat::CSV => write!(f, "csv"),
+        }
+    }
+}
+
+impl FromStr for DumpFormat {
+    type Err = String;
+
+    /// Parse the format name, such as `json`, case is ignored.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Ok(DumpFormat::TEXT),
+            "json" => Ok(DumpFormat::JSON),
+            "csv" => Ok(DumpFormat::CSV),
+            _ => Err(format!("unknown format \"{}\", expected text, json or csv", name)),
+        }
+    }
+}
+
+/// The columns of CSV dump
+const CSV_HEADER: &str = "file,line,column,end_line,end_column,offset,end_offset,kind,value,lexeme,literal,width";
+
+/// Dump tokens in `format`.
+///
+/// The JSON and CSV dumps have the same fields for each token:
+///
+/// - `kind`: the name of `TokenType`, such as `KEYWORD`
+/// - `value`: the name of `TokenValue` for the reserved words and symbols, such as `CLASS`, or none for the others
+/// - `lexeme`: the text in source code
+/// - `literal`: the decoded value of literal, such as `10` for `0xA`, or none for the others. The integer is the value in Java,
+///   such as `-1` for `0xFFFFFFFF`, or `-2147483648` for `2147483648` as the operand of unary `-`, and none if it is out of range
+/// - `width`: the width of number literal, such as `long` for `10L`, or none for the others
+/// - the span from `line` and `column` to `end_line` and `end_column` in `file`, which is nested in JSON
+/// - the byte `offset` and the exclusive `end_offset` of the span in the decoded source, see `Scanner::get_source`
+///
+/// # Examples
+/// ```
+/// use mjava_rust::Scanner;
+/// use mjava_rust::dump::{dump_tokens, DumpFormat};
+///
+/// let tokens: Vec<_> = Scanner::from_str("<memory>", "x = 0xA;").filter_map(Result::ok).collect();
+/// let csv = dump_tokens(&tokens, DumpFormat::CSV);
+/// assert_eq!(csv.lines().nth(3), Some("<memory>,1,5,1,7,4,7,INTEGER_LITERAL,,0xA,10,int"));
+/// ```
+pub fn dump_tokens(tokens: &[Token], format: DumpFormat) -> String {
+    match format {
+        DumpFormat::TEXT => tokens.iter().map(|token| format!("{}\n", token)).collect(),
+        DumpFormat::JSON => {
+            let tokens: Vec<Value> = (0..tokens.len()).map(|index| to_json(tokens, index)).collect();
+            format!("{}\n", serde_json::to_string_pretty(&tokens).unwrap_or_default())
+        },
+        DumpFormat::CSV => {
+            let mut csv = format!("{}\n", CSV_HEADER);
+
+            for index in 0..tokens.len() {
+                csv.push_str(&to_csv_record(tokens, index));
+                csv.push('\n');
+            }
+
+            csv
+        },
+    }
+}
+
+fn to_json(tokens: &[Token], index: usize) -> Value {
+    let token = &tokens[index];
+    let loc = token.get_location();
+
+    let literal = match token.get_literal() {
+        Some(Literal::INTEGER(..)) => get_int_value(tokens, index).map_or(Value::Null, |value| json!(value)),
+        Some(Literal::REAL(value, _)) => json!(value),
+        Some(Literal::BOOLEAN(value)) => json!(value),
+        Some(Literal::CHAR(value)) => json!(value),
+        Some(Literal::STRING(value)) | Some(Literal::DOC_COMMENT(value)) => json!(value),
+        None => Value::Null,
+    };
+
+    json!({
+        "kind": get_kind_name(token),
+        "value": get_value_name(token),
+        "lexeme": token.get_name(),
+        "literal": literal,
+        "width": get_width_name(token),
+        "span": {
+            "file": loc.get_file_name(),
+            "line": loc.get_line(),
+            "column": loc.get_column(),
+            "end_line": loc.get_end_line(),
+            "end_column": loc.get_end_column(),
+            "offset": loc.get_offset(),
+            "end_offset": loc.get_end_offset(),
+        },
+    })
+}
+
+fn to_csv_record(tokens: &[Token], index: usize) -> String {
+    let token = &tokens[index];
+    let loc = token.get_location();
+
+    let literal = match token.get_literal() {
+        Some(Literal::INTEGER(..)) => get_int_value(tokens, index).map(|value| value.to_string()).unwrap_or_default(),
+        Some(Literal::REAL(value, _)) => format!("{:?}", value),
+        Some(Literal::BOOLEAN(value)) => value.to_string(),
+        Some(Literal::CHAR(value)) => value.to_string(),
+        Some(Literal::STRING(value)) | Some(Literal::DOC_COMMENT(value)) => value.to_string(),
+        None => String::new(),
+    };
+
+    let fields = [
+        loc.get_file_name().to_string(),
+        loc.get_line().to_string(),
+        loc.get_column().to_string(),
+        loc.get_end_line().to_string(),
+        loc.get_end_column().to_string(),
+        loc.get_offset().to_string(),
+        loc.get_end_offset().to_string(),
+        get_kind_name(token),
+        get_value_name(token).unwrap_or_default(),
+        token.get_name().to_string(),
+        literal,
+        get_width_name(token).unwrap_or_default(),
+    ];
+
+    fields.iter().map(|field| escape_csv_field(field)).collect::<Vec<_>>().join(",")
+}
+
+/// Quote the field if it contains `,`, `"` or line break, and double the `"` in it.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Get the value in Java of the integer literal at `index`, or none if it is out of range.
+fn get_int_value(tokens: &[Token], index: usize) -> Option<i64> {
+    tokens[index].check_int_literal(is_negated(tokens, index)).ok()
+}
+
+fn get_kind_name(token: &Token) -> String {
+    format!("{:?}", token.get_token_type())
+}
+
+fn get_value_name(token: &Token) -> Option<String> {
+    match token.get_token_value() {
+        TokenValue::UNRESERVED => None,
+        value => Some(format!("{:?}", value)),
+    }
+}
+
+fn get_width_name(token: &Token) -> Option<String> {
+    match token.get_literal() {
+        Some(Literal::INTEGER(_, width)) | Some(Literal::REAL(_, width)) => Some(width.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn dump_literals(source: &str) -> Vec<String> {
+        let tokens: Vec<_> = Scanner::from_str("<test>", source).filter_map(Result::ok).collect();
+        let csv = dump_tokens(&tokens, DumpFormat::CSV);
+        csv.lines().skip(1).map(|record| record.split(',').nth(10).unwrap_or_default().to_string()).collect()
+    }
+
+    #[test]
+    fn integers_are_dumped_as_java_values() {
+        assert_eq!(dump_literals("0xFFFFFFFF 0xFFFFFFFFFFFFFFFFL 0x7FFFFFFF"), ["-1", "-1", "2147483647"]);
+        assert_eq!(dump_literals("x = -2147483648;"), ["", "", "", "-2147483648", ""]);
+        assert_eq!(dump_literals("2147483648 1 - 2147483648"), ["", "1", "", ""]);
+    }
+
+    #[test]
+    fn out_of_range_integers_are_null_in_json() {
+        let tokens: Vec<_> = Scanner::from_str("<test>", "0xFFFFFFFF 2147483648").filter_map(Result::ok).collect();
+        let json: Value = serde_json::from_str(&dump_tokens(&tokens, DumpFormat::JSON)).unwrap();
+        assert_eq!(json[0]["literal"], json!(-1));
+        assert_eq!(json[1]["literal"], Value::Null);
+    }
+}
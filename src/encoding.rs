@@ -0,0 +1,234 @@
+//! Detect and decode the encoding of source file.
+
+use encoding_rs::{DecoderResult, Encoding, GB18030, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use std::fmt;
+use std::str::FromStr;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+/// The character encoding of source file
+pub enum SourceEncoding {
+    /// detect the encoding by BOM, otherwise try `UTF8` and then `GB18030`
+    #[default]
+    AUTO,
+    /// UTF-8
+    UTF8,
+    /// UTF-16 little endian
+    UTF16LE,
+    /// UTF-16 big endian
+    UTF16BE,
+    /// GBK, which is also used for GB2312 and CP936
+    GBK,
+    /// GB18030, which is a superset of GBK
+    GB18030,
+}
+
+impl SourceEncoding {
+    fn to_encoding(self) -> &'static Encoding {
+        match self {
+            SourceEncoding::AUTO | SourceEncoding::UTF8 => UTF_8,
+            SourceEncoding::UTF16LE => UTF_16LE,
+            SourceEncoding::UTF16BE => UTF_16BE,
+            SourceEncoding::GBK => GBK,
+            SourceEncoding::GB18030 => GB18030,
+        }
+    }
+
+    fn from_encoding(encoding: &'static Encoding) -> Self {
+        if encoding == UTF_16LE {
+            SourceEncoding::UTF16LE
+        } else if encoding == UTF_16BE {
+            SourceEncoding::UTF16BE
+        } else {
+            SourceEncoding::UTF8
+        }
+    }
+}
+
+impl fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceEncoding::AUTO => write!(f, "auto"),
+            _ => write!(f, "{}", self.to_encoding().name()),
+        }
+    }
+}
+
+impl FromStr for SourceEncoding {
+    type Err = String;
+
+    /// Parse the encoding name, such as `utf-8` or `gbk`, case and `-` / `_` are ignored.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let normalized: String = name.chars().filter(|ch| *ch != '-' && *ch != '_').collect();
+
+        match normalized.to_ascii_lowercase().as_str() {
+            "auto" => Ok(SourceEncoding::AUTO),
+            "utf8" => Ok(SourceEncoding::UTF8),
+            "utf16le" => Ok(SourceEncoding::UTF16LE),
+            "utf16be" => Ok(SourceEncoding::UTF16BE),
+            "gbk" | "gb2312" | "cp936" => Ok(SourceEncoding::GBK),
+            "gb18030" => Ok(SourceEncoding::GB18030),
+            _ => Err(format!("unknown encoding \"{}\", expected auto, utf-8, utf-16le, utf-16be, gbk or gb18030", name)),
+        }
+    }
+}
+
+/// The BOM of GB18030, which is not recognized by `Encoding::for_bom`
+const GB18030_BOM: [u8; 4] = [0x84, 0x31, 0x95, 0x33];
+
+/// The malformed byte sequence and its offset in the decoded source code
+pub type MalformedBytes = (usize, Vec<u8>);
+
+/// Decode the source code in `encoding` into UTF-8, and return it with the actual encoding and the malformed
+/// byte sequences.
+///
+/// The BOM is removed. The malformed byte sequences of UTF-8 are kept as they are, so the scanner reports them at
+/// their own location. Those of the other encodings may contain bytes which are valid UTF-8, such as `0x00` in
+/// a lone surrogate of UTF-16, so they are replaced with U+FFFD, and returned with the offsets of replacements.
+pub fn decode(bytes: &[u8], encoding: SourceEncoding) -> (Vec<u8>, SourceEncoding, Vec<MalformedBytes>) {
+    let (encoding, bom_length) = match encoding {
+        SourceEncoding::AUTO => detect(bytes),
+        _ => match Encoding::for_bom(bytes) {
+            Some((bom_encoding, bom_length)) if bom_encoding == encoding.to_encoding() => (encoding, bom_length),
+            _ if encoding == SourceEncoding::GB18030 && bytes.starts_with(&GB18030_BOM) => (encoding, GB18030_BOM.len()),
+            _ => (encoding, 0),
+        },
+    };
+
+    let bytes = &bytes[bom_length..];
+
+    match encoding {
+        SourceEncoding::AUTO | SourceEncoding::UTF8 => (bytes.to_vec(), SourceEncoding::UTF8, Vec::new()),
+        _ => {
+            let (decoded, malformed) = transcode(bytes, encoding.to_encoding());
+            (decoded, encoding, malformed)
+        },
+    }
+}
+
+/// Detect the encoding, and return it with the length of BOM.
+fn detect(bytes: &[u8]) -> (SourceEncoding, usize) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return (SourceEncoding::from_encoding(encoding), bom_length);
+    }
+
+    if bytes.starts_with(&GB18030_BOM) {
+        return (SourceEncoding::GB18030, GB18030_BOM.len());
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return (SourceEncoding::UTF8, 0);
+    }
+
+    let (_decoded, had_errors) = GB18030.decode_without_bom_handling(bytes);
+
+    if had_errors {
+        // neither of them, report the invalid bytes as UTF-8
+        (SourceEncoding::UTF8, 0)
+    } else {
+        (SourceEncoding::GB18030, 0)
+    }
+}
+
+fn transcode(bytes: &[u8], encoding: &'static Encoding) -> (Vec<u8>, Vec<MalformedBytes>) {
+    let mut malformed = Vec::new();
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut decoded = Vec::with_capacity(bytes.len() * 3 / 2);
+    let mut buffer = [0; 4096];
+    let mut remaining = bytes;
+
+    loop {
+        let (result, read, written) = decoder.decode_to_utf8_without_replacement(remaining, &mut buffer, true);
+        decoded.extend_from_slice(&buffer[..written]);
+
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => {},
+            DecoderResult::Malformed(malformed_length, consumed_length) => {
+                let end = read.saturating_sub(consumed_length as usize);
+                let start = end.saturating_sub(malformed_length as usize);
+                malformed.push((decoded.len(), remaining[start..end].to_vec()));
+                decoded.extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+            },
+        }
+
+        remaining = &remaining[read..];
+    }
+
+    (decoded, malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::INVALID_BYTE_SEQUENCE;
+    use crate::scanner::Scanner;
+
+    /// `中文` in GBK, which is the same in GB18030
+    const GBK_CHINESE: [u8; 4] = [0xD6, 0xD0, 0xCE, 0xC4];
+
+    #[test]
+    fn utf8_bom_is_removed() {
+        for encoding in [SourceEncoding::AUTO, SourceEncoding::UTF8] {
+            assert_eq!(decode(b"\xEF\xBB\xBFint a;", encoding), (b"int a;".to_vec(), SourceEncoding::UTF8, Vec::new()));
+        }
+    }
+
+    #[test]
+    fn gb18030_bom_is_removed() {
+        let bytes = [&GB18030_BOM[..], &GBK_CHINESE[..]].concat();
+
+        for encoding in [SourceEncoding::AUTO, SourceEncoding::GB18030] {
+            assert_eq!(decode(&bytes, encoding), ("中文".as_bytes().to_vec(), SourceEncoding::GB18030, Vec::new()));
+        }
+
+        // the BOM of GB18030 is only removed for GB18030
+        let (decoded, _, _) = decode(&bytes, SourceEncoding::GBK);
+        assert!(!String::from_utf8(decoded).unwrap().starts_with('中'));
+    }
+
+    #[test]
+    fn utf16_is_detected_by_bom() {
+        let bytes = [0xFF, 0xFE, b'a', 0x00, 0x2D, 0x4E];
+        assert_eq!(decode(&bytes, SourceEncoding::AUTO), ("a中".as_bytes().to_vec(), SourceEncoding::UTF16LE, Vec::new()));
+    }
+
+    #[test]
+    fn gbk_bytes_are_detected() {
+        let bytes = [&b"// "[..], &GBK_CHINESE[..], &b"\nint a;"[..]].concat();
+        let (decoded, encoding, malformed) = decode(&bytes, SourceEncoding::AUTO);
+
+        assert_eq!(String::from_utf8(decoded).unwrap(), "// 中文\nint a;");
+        assert_eq!(encoding, SourceEncoding::GB18030);
+        assert!(malformed.is_empty());
+
+        // valid UTF-8 is never taken as GB18030
+        assert_eq!(decode("中文".as_bytes(), SourceEncoding::AUTO).1, SourceEncoding::UTF8);
+    }
+
+    #[test]
+    fn malformed_bytes_are_replaced() {
+        let (decoded, _, malformed) = decode(&[b'a', 0x81, b' '], SourceEncoding::GBK);
+        assert_eq!(String::from_utf8(decoded).unwrap(), "a\u{FFFD} ");
+        assert_eq!(malformed, [(1, vec![0x81])]);
+
+        // the lone surrogate contains `0x00`, which would be valid UTF-8
+        let (decoded, _, malformed) = decode(&[0x00, 0xD8, b'a', 0x00], SourceEncoding::UTF16LE);
+        assert_eq!(String::from_utf8(decoded).unwrap(), "\u{FFFD}a");
+        assert_eq!(malformed, [(0, vec![0x00, 0xD8])]);
+    }
+
+    #[test]
+    fn malformed_gbk_bytes_are_reported_at_their_column() {
+        // `s = "中文\x81";`, where the columns are counted by characters
+        let bytes = [&b"s = \""[..], &GBK_CHINESE[..], &[0x81][..], &b"\";"[..]].concat();
+        let mut scanner = Scanner::from_reader_with_encoding("<test>", &bytes[..], SourceEncoding::GBK).unwrap();
+        let _tokens: Vec<_> = scanner.by_ref().collect();
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].get_code(), INVALID_BYTE_SEQUENCE);
+        assert_eq!((diagnostics[0].get_location().get_line(), diagnostics[0].get_location().get_column()), (1, 8));
+    }
+}
@@ -0,0 +1,290 @@
+//! Format the source code by its tokens and their trivia.
+
+use crate::token::*;
+
+/// The indentation of one level of braces
+const INDENT: &str = "    ";
+
+/// Format the source code which `tokens` are scanned from, the tokens should be scanned with trivia kept,
+/// and `eof_trivia` is the trivia after the last token got by `Scanner::get_eof_trivia`.
+///
+/// Only the layout of lines changes: lines are indented by the depth of braces, and the lines which continue
+/// an unfinished statement are indented one more level. Trailing whitespace is removed, at most one blank line
+/// is kept in a row, and the source code ends with exactly one newline. Tokens on the same line and the text of
+/// comments are kept as they are.
+///
+/// # Examples
+/// ```
+/// use mjava_rust::Scanner;
+/// use mjava_rust::format::format_tokens;
+///
+/// let mut scanner = Scanner::from_str("<memory>", "class Main {\nint a;  \n\n\n}");
+/// scanner.set_trivia(true);
+/// let tokens: Vec<_> = scanner.by_ref().filter_map(Result::ok).collect();
+/// assert_eq!(format_tokens(&tokens, scanner.get_eof_trivia()), "class Main {\n    int a;\n\n}\n");
+///
+/// let mut scanner = Scanner::from_str("<memory>", "  // only comment  \n");
+/// scanner.set_trivia(true);
+/// let tokens: Vec<_> = scanner.by_ref().filter_map(Result::ok).collect();
+/// assert_eq!(format_tokens(&tokens, scanner.get_eof_trivia()), "// only comment\n");
+/// ```
+pub fn format_tokens(tokens: &[Token], eof_trivia: &str) -> String {
+    let mut source = String::new();
+    let mut depth: usize = 0;
+    let mut paren_depth: usize = 0;
+    let mut line_start = true;
+    let mut previous: Option<&Token> = None;
+
+    for token in tokens {
+        match token.get_token_value() {
+            TokenValue::RBRACE => depth = depth.saturating_sub(1),
+            TokenValue::RPAREN => paren_depth = paren_depth.saturating_sub(1),
+            _ => {},
+        }
+
+        if line_start {
+            let indent = INDENT.repeat(depth + is_continued(token, previous, paren_depth) as usize);
+            push_leading_trivia(&mut source, token.get_leading_trivia(), &indent);
+            push_name(&mut source, token, &indent);
+        } else {
+            source.push_str(token.get_leading_trivia());
+            source.push_str(token.get_name());
+        }
+
+        match token.get_token_value() {
+            TokenValue::LBRACE => depth += 1,
+            TokenValue::LPAREN => paren_depth += 1,
+            _ => {},
+        }
+
+        line_start = push_trailing_trivia(&mut source, token.get_trailing_trivia(), &INDENT.repeat(depth));
+        previous = Some(token);
+    }
+
+    if line_start {
+        push_leading_trivia(&mut source, eof_trivia, &INDENT.repeat(depth));
+    } else {
+        source.push_str(eof_trivia);
+    }
+
+    let length = source.trim_end().len();
+    source.truncate(length);
+
+    if !source.is_empty() {
+        source.push('\n');
+    }
+
+    source
+}
+
+/// Check if the line starting with `token` continues the unfinished statement before it.
+fn is_continued(token: &Token, previous: Option<&Token>, paren_depth: usize) -> bool {
+    if let TokenValue::LBRACE | TokenValue::RBRACE = token.get_token_value() {
+        return false;
+    }
+
+    paren_depth > 0 || previous.is_some_and(|previous| {
+        !matches!(previous.get_token_value(), TokenValue::SEMICOLON | TokenValue::LBRACE | TokenValue::RBRACE)
+            && !matches!(previous.get_token_type(), TokenType::DOC_COMMENT)
+    })
+}
+
+/// Push the leading trivia of the token at the start of line, whose last line is the indentation before it.
+fn push_leading_trivia(source: &mut String, trivia: &str, indent: &str) {
+    let (lines, last) = match trivia.rsplit_once('\n') {
+        Some((lines, last)) => (Some(lines), last),
+        None => (None, trivia),
+    };
+
+    let in_comment = lines.is_some_and(|lines| push_trivia_lines(source, lines.split('\n'), indent, false));
+    push_line_prefix(source, last, indent, in_comment);
+}
+
+/// Push the trailing trivia, and return if the next token is at the start of line.
+fn push_trailing_trivia(source: &mut String, trivia: &str, indent: &str) -> bool {
+    let (first, rest) = match trivia.split_once('\n') {
+        Some(split) => split,
+        None => {
+            source.push_str(trivia);
+            return false;
+        },
+    };
+
+    source.push_str(first.trim_end());
+    source.push('\n');
+
+    let in_comment = is_in_block_comment(first, false);
+    let (lines, last) = match rest.rsplit_once('\n') {
+        Some((lines, last)) => (Some(lines), last),
+        None => (None, rest),
+    };
+
+    let in_comment = match lines {
+        Some(lines) => push_trivia_lines(source, lines.split('\n'), indent, in_comment),
+        None => in_comment,
+    };
+
+    if last.is_empty() {
+        return true;
+    }
+
+    push_line_prefix(source, last, indent, in_comment);
+    false
+}
+
+/// Push the whole lines of trivia, and return if the last one ends inside a block comment.
+///
+/// The lines are indented by `indent`, except those inside a block comment which do not start with `*`.
+fn push_trivia_lines<'a>(source: &mut String, lines: impl Iterator<Item = &'a str>, indent: &str, mut in_comment: bool) -> bool {
+    for line in lines {
+        let text = line.trim();
+
+        if text.is_empty() && !in_comment {
+            if !source.is_empty() && !source.ends_with("\n\n") {
+                source.push('\n');
+            }
+
+            continue;
+        }
+
+        if in_comment {
+            push_comment_line(source, line, indent);
+        } else {
+            source.push_str(indent);
+            source.push_str(text);
+        }
+
+        source.push('\n');
+        in_comment = is_in_block_comment(text, in_comment);
+    }
+
+    in_comment
+}
+
+/// Push the trivia before the token on the same line.
+fn push_line_prefix(source: &mut String, prefix: &str, indent: &str, in_comment: bool) {
+    if in_comment && !prefix.trim_start().starts_with('*') {
+        source.push_str(prefix);
+    } else if in_comment {
+        source.push_str(indent);
+        source.push(' ');
+        source.push_str(prefix.trim_start());
+    } else {
+        source.push_str(indent);
+        source.push_str(prefix.trim_start());
+    }
+}
+
+/// Push the name of token at the start of line, the lines of doc comment are aligned with its first line.
+fn push_name(source: &mut String, token: &Token, indent: &str) {
+    let mut lines = token.get_name().split('\n');
+
+    source.push_str(lines.next().unwrap_or_default());
+
+    for line in lines {
+        source.push('\n');
+        push_comment_line(source, line, indent);
+    }
+}
+
+/// Push the line inside a block comment, which is aligned with the comment start if it starts with `*`.
+fn push_comment_line(source: &mut String, line: &str, indent: &str) {
+    let text = line.trim_start();
+
+    if text.starts_with('*') {
+        source.push_str(indent);
+        source.push(' ');
+        source.push_str(text.trim_end());
+    } else {
+        source.push_str(line.trim_end());
+    }
+}
+
+/// Check if the line of trivia ends inside a block comment, `in_comment` is whether it starts inside one.
+fn is_in_block_comment(line: &str, mut in_comment: bool) -> bool {
+    let mut rest = line;
+
+    loop {
+        if in_comment {
+            match rest.find("*/") {
+                Some(end) => {
+                    rest = &rest[end + 2..];
+                    in_comment = false;
+                },
+                None => return true,
+            }
+        } else {
+            rest = rest.trim_start();
+
+            match rest.strip_prefix("/*") {
+                Some(comment) => {
+                    rest = comment;
+                    in_comment = true;
+                },
+                None => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn format(source: &str) -> String {
+        let mut scanner = Scanner::from_str("<test>", source);
+        scanner.set_trivia(true);
+        let tokens: Vec<Token> = scanner.by_ref().filter_map(Result::ok).collect();
+        format_tokens(&tokens, scanner.get_eof_trivia())
+    }
+
+    #[test]
+    fn lines_are_indented_by_braces() {
+        let source = "class A {\nvoid f() {\nif (a) {\nb = 1;\n}\n}\n}";
+        assert_eq!(format(source), "class A {\n    void f() {\n        if (a) {\n            b = 1;\n        }\n    }\n}\n");
+    }
+
+    #[test]
+    fn unfinished_statements_are_continued() {
+        assert_eq!(format("class A {\nint a = 1 +\n2;\nint b;\n}"), "class A {\n    int a = 1 +\n        2;\n    int b;\n}\n");
+        assert_eq!(format("f(a,\nb);"), "f(a,\n    b);\n");
+    }
+
+    #[test]
+    fn blank_lines_and_trailing_whitespace_are_trimmed() {
+        assert_eq!(format("\n\nint a;   \n\n\n\nint b;\t\n\n"), "int a;\n\nint b;\n");
+        assert_eq!(format(""), "");
+        assert_eq!(format(" \n\n "), "");
+    }
+
+    #[test]
+    fn tokens_on_the_same_line_are_kept() {
+        assert_eq!(format("  int   a=1 ;  int b;"), "int   a=1 ;  int b;\n");
+    }
+
+    #[test]
+    fn comments_are_reindented() {
+        let source = "class A {\n// line\n      /* block\n      * star\n  plain\n      */\nint a; // trailing\n}\n// end";
+        assert_eq!(format(source), "class A {\n    // line\n    /* block\n     * star\n  plain\n     */\n    int a; // trailing\n}\n// end\n");
+    }
+
+    #[test]
+    fn doc_comments_are_aligned() {
+        let source = "class A {\n        /**\n         * doc\n         */\nint a;\n}";
+        assert_eq!(format(source), "class A {\n    /**\n     * doc\n     */\n    int a;\n}\n");
+    }
+
+    #[test]
+    fn comment_only_source_is_kept() {
+        assert_eq!(format("   // only comment   \n"), "// only comment\n");
+        assert_eq!(format("/* a\n * b\n */"), "/* a\n * b\n */\n");
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let source = "class A {\n/** doc */\nint a = 1 +\n2; // c\n\n\nvoid f() { if (a) {\nb();\n} }\n}\n";
+        let formatted = format(source);
+        assert_eq!(format(&formatted), formatted);
+    }
+}
@@ -0,0 +1,34 @@
+#![warn(missing_docs)]
+//! Lexical analyzer of MJava, a subset of Java.
+//!
+//! # Examples
+//! ```no_run
+//! use mjava_rust::Scanner;
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let scanner = Scanner::new("./test.mjava".to_string())?;
+//!
+//! for token in scanner.filter_map(Result::ok) {
+//!     println!("{}", token);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod symbol;
+pub mod token;
+pub mod dictionary;
+pub mod diagnostic;
+pub mod encoding;
+pub mod scanner;
+pub mod doc;
+pub mod dump;
+pub mod format;
+pub mod syntax;
+
+pub use crate::diagnostic::{Diagnostic, LexError, Severity};
+pub use crate::dictionary::{Dialect, Dictionary, TokenInfo};
+pub use crate::encoding::SourceEncoding;
+pub use crate::scanner::Scanner;
+pub use crate::symbol::{FileId, Symbol};
+pub use crate::token::{Literal, NumberWidth, Token, TokenKind, TokenLocation, TokenType, TokenValue};
@@ -1,45 +1,580 @@
-mod token;
-mod dictionary;
-mod scanner;
-use crate::token::*;
-use crate::scanner::*;
+use mjava_rust::doc::*;
+use mjava_rust::diagnostic::*;
+use mjava_rust::dictionary::*;
+use mjava_rust::dump::*;
+use mjava_rust::encoding::*;
+use mjava_rust::format::*;
+use mjava_rust::scanner::*;
+use mjava_rust::token::*;
+use std::collections::HashSet;
 use std::env;
+use std::fmt;
+use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+const USAGE: &str = "\
+Usage: mjava-rust <COMMAND> [OPTIONS] <FILE>...
+
+Commands:
+    lex      Write the tokens of FILE
+    doc      Write the API reference of the doc comments in FILE as Markdown
+    check    Report the lexical errors and out of range literals in FILE
+    fmt      Reindent FILE and write the formatted source code, only one FILE is allowed
+    parse    Parse FILE (not supported yet)
+    run      Run FILE (not supported yet)
+    help     Print this help
+
+FILE is a source file, a glob pattern such as 'src/*.mjava', or a directory which is searched
+recursively for .mjava and .java files. The outputs of all files are written together, and a
+summary is printed if there is more than one file. FILE '-' reads the source code from stdin,
+which is named '<stdin>' in diagnostics.
+
+Options:
+    -o, --output <FILE>        Write the output of lex, doc or fmt to FILE rather than stdout, '-' is stdout
+        --format <FORMAT>      The format of tokens written by lex, text, json or csv [default: text]
+    -q, --quiet                Do not print diagnostics, only report them by the exit status
+        --encoding <ENCODING>  auto, utf-8, utf-16le, utf-16be, gbk or gb18030 [default: auto]
+        --dialect <DIALECT>    minijava, mjava or java-subset [default: mjava]
+        --spec <FILE>          Load extra keywords and operators from the JSON spec FILE
+    -h, --help                 Print this help
+
+Exit status:
+    0  Success
+    1  Any FILE has errors
+    2  Invalid usage
+    3  I/O error
+    4  The command is not supported yet
+";
+
+/// All files are scanned without error
+const EXIT_SUCCESS: i32 = 0;
+/// Some file has lexical errors or out of range literals
+const EXIT_ERROR: i32 = 1;
+/// The command line is invalid
+const EXIT_USAGE: i32 = 2;
+/// Some file or the output can not be read or written
+const EXIT_IO: i32 = 3;
+/// The command needs the parts of compiler which do not exist yet
+const EXIT_UNSUPPORTED: i32 = 4;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// The subcommand of CLI
+enum Command {
+    LEX,
+    DOC,
+    CHECK,
+    FMT,
+    PARSE,
+    RUN,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::LEX => write!(f, "lex"),
+            Command::DOC => write!(f, "doc"),
+            Command::CHECK => write!(f, "check"),
+            Command::FMT => write!(f, "fmt"),
+            Command::PARSE => write!(f, "parse"),
+            Command::RUN => write!(f, "run"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "lex" => Ok(Command::LEX),
+            "doc" => Ok(Command::DOC),
+            "check" => Ok(Command::CHECK),
+            "fmt" => Ok(Command::FMT),
+            "parse" => Ok(Command::PARSE),
+            "run" => Ok(Command::RUN),
+            _ => Err(format!("unknown command \"{}\"", name)),
+        }
+    }
+}
+
+/// The options parsed from the command line
+struct Options {
+    command_: Command,
+    inputs_: Vec<String>,
+    output_: Option<String>,
+    format_: DumpFormat,
+    encoding_: SourceEncoding,
+    dialect_: Dialect,
+    spec_file_name_: Option<String>,
+    quiet_: bool,
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let options = match parse_args(env::args().skip(1)) {
+        Err(err) => {
+            eprintln!("error: {}", err);
+            eprintln!("Try 'mjava-rust --help' for more information.");
+            process::exit(EXIT_USAGE);
+        },
+        Ok(None) => {
+            print!("{}", USAGE);
+            process::exit(EXIT_SUCCESS);
+        },
+        Ok(Some(options)) => options,
+    };
+
+    process::exit(run(&options));
+}
+
+/// Parse the command line without the program name, and return `None` if the help is asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut positionals = Vec::new();
+    let mut output = None;
+    let mut format = DumpFormat::TEXT;
+    let mut encoding = SourceEncoding::AUTO;
+    let mut dialect = Dialect::MJAVA;
+    let mut spec_file_name = None;
+    let mut quiet = false;
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        } else if arg == "-q" || arg == "--quiet" {
+            quiet = true;
+        } else if let Some(value) = get_option_value(&arg, &["-o", "--output"], &mut args)? {
+            output = Some(value);
+        } else if let Some(value) = get_option_value(&arg, &["--format"], &mut args)? {
+            format = value.parse()?;
+        } else if let Some(value) = get_option_value(&arg, &["--encoding"], &mut args)? {
+            encoding = value.parse()?;
+        } else if let Some(value) = get_option_value(&arg, &["--dialect"], &mut args)? {
+            dialect = value.parse()?;
+        } else if let Some(value) = get_option_value(&arg, &["--spec"], &mut args)? {
+            spec_file_name = Some(value);
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!("unknown option \"{}\"", arg));
+        } else {
+            positionals.push(arg);
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
 
-    if args.len() < 2 {
-        panic!("Please enter file name!");
+    let command = match positionals.next() {
+        None => return Err("missing command".to_string()),
+        Some(command) if command == "help" => return Ok(None),
+        Some(command) => command.parse()?,
+    };
+
+    let inputs: Vec<String> = positionals.collect();
+
+    if inputs.is_empty() {
+        return Err(format!("missing the source file of {}", command));
     }
 
-    if args.len() > 3 {
-        panic!("Many argument!");
+    Ok(Some(Options {
+        command_: command,
+        inputs_: inputs,
+        output_: output,
+        format_: format,
+        encoding_: encoding,
+        dialect_: dialect,
+        spec_file_name_: spec_file_name,
+        quiet_: quiet,
+    }))
+}
+
+/// Get the value of option `name value`, or `--name=value` for the long name,
+/// or `None` if `arg` is not the option.
+fn get_option_value(arg: &str, names: &[&str], args: &mut impl Iterator<Item = String>) -> Result<Option<String>, String> {
+    for name in names {
+        if arg == *name {
+            return args.next().map(Some).ok_or_else(|| format!("missing the value of {}", name));
+        }
+
+        if let Some(value) = arg.strip_prefix(name).and_then(|value| value.strip_prefix('=')).filter(|_| name.starts_with("--")) {
+            return Ok(Some(value.to_string()));
+        }
     }
 
-    let mut file;
-    let file_name;
+    Ok(None)
+}
 
-    if args.len() == 3 {
-        file_name = args[2].to_owned();
+/// The file name which means stdin as input or stdout as output
+const STANDARD_STREAM: &str = "-";
+/// The pseudo file name of stdin in diagnostics
+const STDIN_FILE_NAME: &str = "<stdin>";
+
+/// The extensions of source files searched in directories
+const SOURCE_EXTENSIONS: [&str; 2] = ["mjava", "java"];
+
+/// The counts of files, tokens and diagnostics reported in the summary
+#[derive(Default)]
+struct Summary {
+    files_: usize,
+    unreadable_files_: usize,
+    tokens_: usize,
+    errors_: usize,
+    warnings_: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} processed, {}, {}, {}", plural(self.files_, "file"), plural(self.tokens_, "token"),
+                plural(self.errors_, "error"), plural(self.warnings_, "warning"))?;
+
+        if self.unreadable_files_ > 0 {
+            write!(f, ", {} can not be read", plural(self.unreadable_files_, "file"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Summary {
+    /// Get the exit status, I/O errors take precedence over the errors in source files.
+    fn get_exit_status(&self) -> i32 {
+        if self.unreadable_files_ > 0 {
+            EXIT_IO
+        } else if self.errors_ > 0 {
+            EXIT_ERROR
+        } else {
+            EXIT_SUCCESS
+        }
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, word)
     } else {
-        file_name = "./TokenOut.txt".to_string();
+        format!("{} {}s", count, word)
+    }
+}
+
+/// Run the command, and return the exit status.
+fn run(options: &Options) -> i32 {
+    if let Command::PARSE | Command::RUN = options.command_ {
+        eprintln!("error: {} is not supported yet, there is only the lexer in this front-end", options.command_);
+        return EXIT_UNSUPPORTED;
     }
 
-    file = match File::create(file_name) {
-        Err(err) => panic!("Can not create {}, because {}.", args[2], err.to_string()),
-        Ok(file) => file,
+    let file_names = match expand_inputs(&options.inputs_) {
+        Err(status) => return status,
+        Ok(file_names) => file_names,
     };
 
-    let mut scanner = Scanner::new(args[1].to_owned());
+    if options.command_ == Command::FMT && file_names.len() != 1 {
+        eprintln!("error: fmt takes exactly one source file, but {} are given", file_names.len());
+        return EXIT_USAGE;
+    }
 
-    loop {
-        match scanner.get_token().get_token_type() {
-            TokenType::END_OF_FILE => break,
-            _ => {},
+    let dictionary = match load_dictionary(options) {
+        Err(status) => return status,
+        Ok(dictionary) => dictionary,
+    };
+
+    let mut tokens = Vec::new();
+    let mut doc_items = Vec::new();
+    let mut eof_trivia = String::new();
+    let mut summary = Summary::default();
+
+    for file_name in &file_names {
+        let file = match scan_file(options, file_name, dictionary.to_owned()) {
+            Err(err) => {
+                eprintln!("error: can not open {}, because {}", get_display_name(file_name), err);
+                summary.unreadable_files_ += 1;
+                continue;
+            },
+            Ok(result) => result,
+        };
+
+        if !options.quiet_ {
+            for diagnostic in &file.diagnostics_ {
+                eprintln!("{}", diagnostic);
+            }
         }
 
-        file.write_all(format!("{}\n",scanner.get_next_token().to_string()).as_bytes()).unwrap();
+        summary.files_ += 1;
+        summary.tokens_ += file.tokens_.len();
+        summary.errors_ += file.diagnostics_.iter().filter(|diagnostic| diagnostic.is_error()).count();
+        summary.warnings_ += file.diagnostics_.iter().filter(|diagnostic| !diagnostic.is_error()).count();
+
+        // doc comments are attached within each file, so that they do not run into the declarations of the next one
+        if options.command_ == Command::DOC {
+            doc_items.extend(attach_doc_comments(&file.tokens_));
+        }
+
+        tokens.extend(file.tokens_);
+        eof_trivia = file.eof_trivia_;
+    }
+
+    let output = match options.command_ {
+        Command::LEX => Some(dump_tokens(&tokens, options.format_)),
+        Command::DOC => Some(to_markdown(&doc_items)),
+        // the source code which has lexical errors is not formatted, or the errors would be hidden in trivia
+        Command::FMT if summary.errors_ == 0 && summary.unreadable_files_ == 0 => Some(format_tokens(&tokens, &eof_trivia)),
+        _ => None,
+    };
+
+    if let Some(output) = output {
+        if let Err(err) = write_output(options, &output) {
+            eprintln!("error: can not write the output, because {}", err);
+            return EXIT_IO;
+        }
+    }
+
+    if file_names.len() > 1 && !options.quiet_ {
+        eprintln!("summary: {}", summary);
+    }
+
+    summary.get_exit_status()
+}
+
+/// The tokens of source file and the diagnostics reported while scanning it
+struct ScannedFile {
+    tokens_: Vec<Token>,
+    diagnostics_: Vec<Diagnostic>,
+    // the trivia after the last token, which is kept only for fmt
+    eof_trivia_: String,
+}
+
+/// Scan the source file, and return its tokens with the diagnostics reported.
+fn scan_file(options: &Options, file_name: &str, dictionary: Dictionary) -> io::Result<ScannedFile> {
+    let mut scanner = if file_name == STANDARD_STREAM {
+        Scanner::from_reader_with_encoding(STDIN_FILE_NAME, io::stdin().lock(), options.encoding_)?
+    } else {
+        Scanner::new_with_encoding(file_name.to_string(), options.encoding_)?
+    };
+
+    scanner.set_dictionary(dictionary);
+    scanner.set_trivia(options.command_ == Command::FMT);
+
+    let tokens: Vec<_> = scanner.by_ref().filter_map(Result::ok).collect();
+    let mut diagnostics = scanner.get_diagnostics().to_vec();
+
+    // there is no parser yet, so check the range of integer literals here
+    if options.command_ != Command::FMT {
+        diagnostics.extend(tokens.iter().enumerate()
+            .filter(|(_, token)| matches!(token.get_token_type(), TokenType::INTEGER_LITERAL))
+            .filter_map(|(index, token)| token.check_int_literal(is_negated(&tokens, index)).err()));
+    }
+
+    Ok(ScannedFile {
+        tokens_: tokens,
+        diagnostics_: diagnostics,
+        eof_trivia_: scanner.get_eof_trivia().to_string(),
+    })
+}
+
+/// Expand the inputs into source files, the directories are searched recursively and the glob patterns are matched.
+///
+/// The inputs which are neither directories nor patterns, including `-` for stdin, are kept as they are,
+/// so that the missing files are reported when they are opened. Every file appears only once, in the order it is first found.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<String>, i32> {
+    let mut file_names = Vec::new();
+
+    for input in inputs {
+        let path = Path::new(input);
+
+        if path.is_dir() {
+            let count = file_names.len();
+
+            find_source_files(path, &mut file_names).map_err(|err| {
+                eprintln!("error: can not read directory {}, because {}", input, err);
+                EXIT_IO
+            })?;
+
+            if file_names.len() == count {
+                eprintln!("error: no source files found in {}", input);
+                return Err(EXIT_USAGE);
+            }
+        } else if input != STANDARD_STREAM && !path.exists() && input.contains(['*', '?', '[']) {
+            let paths = glob::glob(input).map_err(|err| {
+                eprintln!("error: invalid pattern \"{}\", because {}", input, err);
+                EXIT_USAGE
+            })?;
+
+            let count = file_names.len();
+
+            for path in paths {
+                match path {
+                    Ok(path) if path.is_dir() => {},
+                    Ok(path) => file_names.push(path.to_string_lossy().into_owned()),
+                    Err(err) => {
+                        eprintln!("error: can not read {}, because {}", err.path().display(), err.error());
+                        return Err(EXIT_IO);
+                    },
+                }
+            }
+
+            if file_names.len() == count {
+                eprintln!("error: no file matches \"{}\"", input);
+                return Err(EXIT_USAGE);
+            }
+        } else {
+            file_names.push(input.to_owned());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    file_names.retain(|file_name| seen.insert(file_name.to_owned()));
+    Ok(file_names)
+}
+
+/// Get the name of source file used in messages, which is `<stdin>` for `-`.
+fn get_display_name(file_name: &str) -> &str {
+    if file_name == STANDARD_STREAM {
+        STDIN_FILE_NAME
+    } else {
+        file_name
+    }
+}
+
+/// Find the source files in directory and its subdirectories, in the order of their paths.
+fn find_source_files(dir: &Path, file_names: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            find_source_files(&path, file_names)?;
+        } else if path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension)) {
+            file_names.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the dictionary of dialect, which is extended by the spec file if there is one.
+fn load_dictionary(options: &Options) -> Result<Dictionary, i32> {
+    let mut dictionary = Dictionary::for_dialect(options.dialect_);
+
+    if let Some(spec_file_name) = &options.spec_file_name_ {
+        let spec = fs::read_to_string(spec_file_name).map_err(|err| {
+            eprintln!("error: can not open {}, because {}", spec_file_name, err);
+            EXIT_IO
+        })?;
+
+        dictionary.load_spec(&spec).map_err(|errors| {
+            for err in errors {
+                eprintln!("{}: {}", spec_file_name, err);
+            }
+
+            EXIT_USAGE
+        })?;
+    }
+
+    Ok(dictionary)
+}
+
+/// Write the output to the output file, or stdout if there is no output file or it is `-`.
+fn write_output(options: &Options, output: &str) -> io::Result<()> {
+    let mut writer: Box<dyn Write> = match options.output_.as_deref() {
+        Some(file_name) if file_name != STANDARD_STREAM => Box::new(BufWriter::new(File::create(file_name)?)),
+        _ => Box::new(io::stdout().lock()),
+    };
+
+    writer.write_all(output.as_bytes())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let options = parse(&["lex", "-q", "--format", "json", "-o", "out.json", "--dialect=java-subset", "a.mjava", "-"])
+            .unwrap().unwrap();
+
+        assert_eq!(options.command_, Command::LEX);
+        assert_eq!(options.inputs_, ["a.mjava", "-"]);
+        assert_eq!(options.output_.as_deref(), Some("out.json"));
+        assert_eq!(options.format_, DumpFormat::JSON);
+        assert_eq!(options.dialect_, Dialect::JAVA_SUBSET);
+        assert_eq!(options.encoding_, SourceEncoding::AUTO);
+        assert!(options.quiet_);
+    }
+
+    #[test]
+    fn long_options_take_values_after_equal_sign() {
+        let options = parse(&["fmt", "--output=", "--encoding=gbk", "--spec=spec.json", "a.mjava"]).unwrap().unwrap();
+
+        assert_eq!(options.output_.as_deref(), Some(""));
+        assert_eq!(options.encoding_, SourceEncoding::GBK);
+        assert_eq!(options.spec_file_name_.as_deref(), Some("spec.json"));
+
+        // only long options can be written with `=`
+        assert_eq!(parse(&["lex", "-o=out", "a.mjava"]).err(), Some("unknown option \"-o=out\"".to_string()));
+    }
+
+    #[test]
+    fn missing_option_values_are_reported() {
+        assert_eq!(parse(&["lex", "a.mjava", "-o"]).err(), Some("missing the value of -o".to_string()));
+        assert_eq!(parse(&["lex", "a.mjava", "--format"]).err(), Some("missing the value of --format".to_string()));
+
+        let mut args = Vec::<String>::new().into_iter();
+        assert_eq!(get_option_value("--output", &["-o", "--output"], &mut args), Err("missing the value of --output".to_string()));
+        assert_eq!(get_option_value("--outputs=a", &["-o", "--output"], &mut args), Ok(None));
+    }
+
+    #[test]
+    fn invalid_usage_is_reported() {
+        assert_eq!(parse(&[]).err(), Some("missing command".to_string()));
+        assert_eq!(parse(&["compile", "a.mjava"]).err(), Some("unknown command \"compile\"".to_string()));
+        assert_eq!(parse(&["lex"]).err(), Some("missing the source file of lex".to_string()));
+        assert_eq!(parse(&["lex", "--verbose", "a.mjava"]).err(), Some("unknown option \"--verbose\"".to_string()));
+        assert!(parse(&["lex", "--format", "xml", "a.mjava"]).is_err());
+        assert!(parse(&["lex", "--dialect", "kotlin", "a.mjava"]).is_err());
+    }
+
+    #[test]
+    fn help_is_asked_for() {
+        assert!(parse(&["help"]).unwrap().is_none());
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["lex", "a.mjava", "-h"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn exit_status_follows_the_summary() {
+        let mut summary = Summary::default();
+        assert_eq!(summary.get_exit_status(), EXIT_SUCCESS);
+
+        summary.warnings_ = 1;
+        assert_eq!(summary.get_exit_status(), EXIT_SUCCESS);
+
+        summary.errors_ = 1;
+        assert_eq!(summary.get_exit_status(), EXIT_ERROR);
+
+        summary.unreadable_files_ = 1;
+        assert_eq!(summary.get_exit_status(), EXIT_IO);
+    }
+
+    #[test]
+    fn unsupported_commands_exit_without_reading_files() {
+        for command in ["parse", "run"] {
+            let options = parse(&[command, "missing.mjava"]).unwrap().unwrap();
+            assert_eq!(run(&options), EXIT_UNSUPPORTED);
+        }
+    }
+
+    #[test]
+    fn unreadable_files_exit_with_io_error() {
+        let options = parse(&["check", "-q", "missing file.mjava"]).unwrap().unwrap();
+        assert_eq!(run(&options), EXIT_IO);
     }
 }
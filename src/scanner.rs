@@ -1,10 +1,17 @@
+//! Scanner which splits source file into tokens.
+
 use crate::token::*;
 use crate::dictionary::*;
+use crate::diagnostic::*;
+use crate::encoding::*;
+use crate::symbol::*;
+use std::collections::HashMap;
+use std::io;
 use std::io::prelude::*;
-use std::fs::File;
-use std::io::SeekFrom;
+use std::fs;
 
 #[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
 /// The state of lexical analysis.
 enum State {
     /// original state
@@ -21,15 +28,24 @@ enum State {
     STRING_LITERAL,
     /// parse `TokenType::OPERATION` or `TokenType::DELIMITER`
     OPERATION,
+    /// parse `TokenType::DOC_COMMENT`
+    DOC_COMMENT,
 }
 
-
 /// Lexical scanner
 pub struct Scanner {
-    file_name_: String,
-    file_: File,
+    file_id_: FileId,
+    source_: Vec<u8>,
+    position_: usize,
+    offset_: usize,
+    encoding_: SourceEncoding,
+    // the malformed byte sequences replaced with U+FFFD by their offsets
+    malformed_bytes_: HashMap<usize, Vec<u8>>,
     line_: i32,
     column_: i32,
+    end_line_: i32,
+    end_column_: i32,
+    end_offset_: usize,
     loc_: TokenLocation,
     current_char_: char,
     state_: State,
@@ -38,28 +54,127 @@ pub struct Scanner {
     buffer_: String,
     eof_flag_: bool,
     error_flag_: bool,
+    replaced_flag_: bool,
+    trivia_flag_: bool,
+    leading_trivia_: String,
+    eof_trivia_: String,
+    pending_error_: Option<LexError>,
+    diagnostics_: Vec<Diagnostic>,
 }
 
 impl Scanner {
-    /// New scanner by the name of source file.
+    /// New scanner by the name of source file, whose encoding is detected automatically.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the source file can not be read.
     ///
     /// # Examples
+    /// ```no_run
+    /// use mjava_rust::Scanner;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let source_file_name = "./test.mjava".to_string();
+    /// let mut scanner = Scanner::new(source_file_name)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    /// let source_file_name = "./test.mjava";
-    /// let mut scanner = Scanner::new(source_file_name);
+    pub fn new(file_name: String) -> io::Result<Self> {
+        Scanner::new_with_encoding(file_name, SourceEncoding::AUTO)
+    }
+
+    /// New scanner by the name of source file, which is encoded in `encoding`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the source file can not be read.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use mjava_rust::{Scanner, SourceEncoding};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let source_file_name = "./test.mjava".to_string();
+    /// let mut scanner = Scanner::new_with_encoding(source_file_name, SourceEncoding::GBK)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn new(file_name: String) -> Self {
-        let file = match File::open(file_name.to_owned()) {
-            Err(err) => panic!("When trying to open file {}, because {}, an error occurred.", err.to_string(), &file_name),
-            Ok(file) => file,
-        };
+    pub fn new_with_encoding(file_name: String, encoding: SourceEncoding) -> io::Result<Self> {
+        let bytes = fs::read(&file_name)?;
+
+        Ok(Scanner::with_bytes(file_name, &bytes, encoding))
+    }
+
+    /// New scanner from the source code in memory, `file_name` is the pseudo file name used in `TokenLocation`.
+    ///
+    /// # Examples
+    /// ```
+    /// use mjava_rust::Scanner;
+    ///
+    /// let mut scanner = Scanner::from_str("<memory>", "class Main {}");
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(file_name: &str, source: &str) -> Self {
+        Scanner::with_source(file_name.to_string(), source.as_bytes().to_vec(), SourceEncoding::UTF8)
+    }
+
+    /// New scanner from an arbitrary reader, whose encoding is detected automatically,
+    /// `file_name` is the pseudo file name used in `TokenLocation`.
+    ///
+    /// The reader is read to the end at once, so it does not need to be seekable.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if reading from `reader` fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use mjava_rust::Scanner;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut scanner = Scanner::from_reader("<stdin>", std::io::stdin())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader<R: Read>(file_name: &str, reader: R) -> io::Result<Self> {
+        Scanner::from_reader_with_encoding(file_name, reader, SourceEncoding::AUTO)
+    }
+
+    /// New scanner from an arbitrary reader, which is encoded in `encoding`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if reading from `reader` fails.
+    pub fn from_reader_with_encoding<R: Read>(file_name: &str, mut reader: R, encoding: SourceEncoding) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
 
-        Scanner {
-            file_name_: file_name.to_owned(),
-            file_: file,
+        Ok(Scanner::with_bytes(file_name.to_string(), &bytes, encoding))
+    }
+
+    fn with_bytes(file_name: String, bytes: &[u8], encoding: SourceEncoding) -> Self {
+        let (decoded, encoding, malformed) = decode(bytes, encoding);
+
+        let mut scanner = Scanner::with_source(file_name, decoded, encoding);
+        scanner.malformed_bytes_ = malformed.into_iter().collect();
+        scanner
+    }
+
+    /// New scanner from the whole UTF-8 source code, which is kept in memory so the scanner can look ahead freely.
+    fn with_source(file_name: String, source: Vec<u8>, encoding: SourceEncoding) -> Self {
+        let mut scanner = Scanner {
+            file_id_: FileId::new(&file_name),
+            source_: source,
+            position_: 0,
+            offset_: 0,
+            encoding_: encoding,
+            malformed_bytes_: HashMap::new(),
             line_: 1,
             column_: 0,
-            loc_: TokenLocation::new(file_name, 1, 0),
+            end_line_: 1,
+            end_column_: 0,
+            end_offset_: 0,
+            loc_: TokenLocation::new(FileId::new(&file_name), 1, 0, 0, 0),
             current_char_: Default::default(),
             state_: State::NONE,
             token_: Default::default(),
@@ -67,86 +182,250 @@ impl Scanner {
             buffer_: Default::default(),
             eof_flag_: false,
             error_flag_: false,
-        }
+            replaced_flag_: false,
+            trivia_flag_: false,
+            leading_trivia_: Default::default(),
+            eof_trivia_: Default::default(),
+            pending_error_: None,
+            diagnostics_: Vec::new(),
+        };
+
+        scanner.get_next_char();
+        scanner
+    }
+
+    /// Keep the whitespace and comments as the trivia of tokens, rather than throw them away.
+    ///
+    /// Each token gets the trailing trivia up to the end of line, and the rest is the leading trivia of next token,
+    /// the trivia at the end of file is the trailing trivia of the last token. The source code of a token which
+    /// has a lexical error is kept in the leading trivia of next token. What is left after the last token, such as
+    /// the whole source code which has only comments, or a token with a lexical error at the end of file, is the
+    /// trivia of `END_OF_FILE` token, which is not yielded by the iterator but got by `get_eof_trivia`.
+    /// So for the decoded source code, concatenating the leading trivia, name and trailing trivia of all tokens,
+    /// and then the trivia of end of file reproduces it exactly.
+    ///
+    /// # Examples
+    /// ```
+    /// use mjava_rust::Scanner;
+    ///
+    /// for source in ["int a; // count\n", "// only comment\n", "/* c */", "int a; \"abc"] {
+    ///     let mut scanner = Scanner::from_str("<memory>", source);
+    ///     scanner.set_trivia(true);
+    ///     let mut text: String = scanner.by_ref().filter_map(Result::ok)
+    ///         .map(|token| format!("{}{}{}", token.get_leading_trivia(), token.get_name(), token.get_trailing_trivia()))
+    ///         .collect();
+    ///     text.push_str(scanner.get_eof_trivia());
+    ///     assert_eq!(text, source);
+    /// }
+    /// ```
+    pub fn set_trivia(&mut self, trivia: bool) {
+        self.trivia_flag_ = trivia;
+    }
+
+    /// Get the trivia which is left after the last token, it is complete once `END_OF_FILE` token is scanned.
+    ///
+    /// It is always empty unless the scanner keeps trivia.
+    pub fn get_eof_trivia(&self) -> &str {
+        &self.eof_trivia_
+    }
+
+    /// Scan the source code in `dialect`, whose reserved words, types and operators are recognized.
+    ///
+    /// The default one is `Dialect::MJAVA`.
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dictionary_ = Dictionary::for_dialect(dialect);
+    }
+
+    /// Scan the source code with `dictionary`, such as the one extended by `Dictionary::load_spec`.
+    pub fn set_dictionary(&mut self, dictionary: Dictionary) {
+        self.dictionary_ = dictionary;
+    }
+
+    /// Get the encoding of source code, which is never `SourceEncoding::AUTO`.
+    pub fn get_encoding(&self) -> SourceEncoding {
+        self.encoding_
+    }
+
+    /// Get the decoded source code, which the byte offsets of `TokenLocation` refer to.
+    ///
+    /// It is the source code transcoded into UTF-8 with the BOM removed, so the offsets do not refer to the bytes
+    /// of source file unless it is UTF-8 without BOM. Malformed byte sequences of UTF-8 are kept as they are,
+    /// so it may not be valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// use mjava_rust::Scanner;
+    ///
+    /// let mut scanner = Scanner::from_reader("<memory>", &b"\xEF\xBB\xBFint a;"[..]).unwrap();
+    /// let loc = scanner.get_next_token().get_location().to_owned();
+    /// assert_eq!(&scanner.get_source()[loc.get_offset()..loc.get_end_offset()], b"int");
+    /// ```
+    pub fn get_source(&self) -> &[u8] {
+        &self.source_
     }
 
+    /// Get the location of the current character.
     fn get_token_location(&self) -> TokenLocation {
-        TokenLocation::new(self.file_name_.to_owned(), self.line_, self.column_)
+        TokenLocation::new(self.file_id_, self.line_, self.column_, self.offset_, self.position_ - self.offset_)
     }
 
-    fn make_token(&mut self, token_type: TokenType, token_value: TokenValue, loc: TokenLocation, name: String, symbol_precedence: i32) {
-        self.token_ = Token::new_token(token_type, token_value, loc, name, symbol_precedence);
+    /// Extend `loc` to the last character which has been read, that is the one before the current character.
+    fn get_span_location(&self, loc: TokenLocation) -> TokenLocation {
+        loc.extend_to(self.end_line_, self.end_column_, self.end_offset_)
+    }
+
+    fn make_token(&mut self, token_type: TokenType, token_value: TokenValue, loc: TokenLocation, name: &str, symbol_precedence: i32) {
+        self.token_ = Token::new_token(token_type, token_value, self.get_span_location(loc), name, symbol_precedence);
         self.buffer_.clear();
         self.state_ = State::NONE;
     }
 
-    fn make_int_token(&mut self, loc: TokenLocation, name: String, int_value: i32) {
-        self.token_ = Token::new_int_token(loc, name, int_value);
+    fn make_int_token(&mut self, loc: TokenLocation, name: String, int_value: u64, number_width: NumberWidth) {
+        self.token_ = Token::new_int_token(self.get_span_location(loc), name, int_value, number_width);
         self.buffer_.clear();
         self.state_ = State::NONE;
     }
 
-    fn make_real_token(&mut self, loc: TokenLocation, name: String, real_value: f64) {
-        self.token_ = Token::new_real_token(loc, name, real_value);
+    fn make_real_token(&mut self, loc: TokenLocation, name: String, real_value: f64, number_width: NumberWidth) {
+        self.token_ = Token::new_real_token(self.get_span_location(loc), name, real_value, number_width);
         self.buffer_.clear();
         self.state_ = State::NONE;
     }
 
     fn make_char_token(&mut self, loc: TokenLocation, name: String, char_value: char) {
-        self.token_ = Token::new_char_token(loc, name, char_value);
+        self.token_ = Token::new_char_token(self.get_span_location(loc), name, char_value);
         self.buffer_.clear();
         self.state_ = State::NONE;
     }
 
     fn make_str_token(&mut self, loc: TokenLocation, name: String, str_value: String) {
-        self.token_ = Token::new_str_token(loc, name, str_value);
+        self.token_ = Token::new_str_token(self.get_span_location(loc), name, str_value);
         self.buffer_.clear();
         self.state_ = State::NONE;
     }
 
+    fn make_doc_comment_token(&mut self, loc: TokenLocation, name: String, text: String) {
+        self.token_ = Token::new_doc_comment_token(self.get_span_location(loc), name, text);
+        self.buffer_.clear();
+        self.state_ = State::NONE;
+    }
+
+    /// Decode one UTF-8 encoded character at `offset` of the source.
+    ///
+    /// Return `None` at the end of file, otherwise the decoded character, or the invalid byte sequence,
+    /// together with the number of bytes it takes.
+    fn decode_char(&self, offset: usize) -> Option<(Result<char, Vec<u8>>, usize)> {
+        let bytes = self.source_.get(offset..)?;
+        let first = *bytes.first()?;
+
+        let width = match first {
+            0x00..=0x7F => return Some((Ok(first.into()), 1)),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Some((Err(bytes[..1].to_vec()), 1)),
+        };
+
+        // stop at the first byte which is not a continuation byte, it is left for the next character
+        let length = 1 + bytes[1..width.min(bytes.len())].iter().take_while(|byte| *byte & 0xC0 == 0x80).count();
+
+        match std::str::from_utf8(&bytes[..length]) {
+            Ok(decoded) => Some((Ok(decoded.chars().next().unwrap()), length)),
+            Err(_e) => Some((Err(bytes[..length].to_vec()), length)),
+        }
+    }
+
     fn get_next_char(&mut self) {
-        let mut buffer = [0; 1];
-        match self.file_.read_exact(&mut buffer) {
-            Err(_e) => {
+        let mut invalid_bytes = None;
+
+        if !self.eof_flag_ {
+            self.end_line_ = self.line_;
+            self.end_column_ = self.column_;
+            self.end_offset_ = self.position_;
+        }
+
+        self.offset_ = self.position_;
+
+        match self.decode_char(self.position_) {
+            None => {
                 self.eof_flag_ = true;
-                self.current_char_ = std::char::MAX;
+                self.current_char_ = char::MAX;
+            },
+            Some((decoded, length)) => {
+                match decoded {
+                    Ok(ch) => {
+                        self.current_char_ = ch;
+                        invalid_bytes = self.malformed_bytes_.get(&self.offset_).cloned();
+                    },
+                    Err(bytes) => {
+                        self.current_char_ = char::REPLACEMENT_CHARACTER;
+                        invalid_bytes = Some(bytes);
+                    },
+                }
+
+                self.position_ += length;
             },
-            Ok(()) => self.current_char_ = buffer[0].into(),
         }
 
         if self.current_char_ == '\n' {
-            self.line_ = self.line_ + 1;
+            self.line_ += 1;
             self.column_ = 0;
         } else {
-            self.column_ = self.column_ + 1;
+            self.column_ += 1;
+        }
+
+        self.replaced_flag_ = invalid_bytes.is_some();
+
+        // the replacement character takes the place of the invalid bytes, so scanning can go on
+        if let Some(bytes) = invalid_bytes {
+            let bytes: Vec<String> = bytes.iter().map(|byte| format!("\\x{:02X}", byte)).collect();
+            let loc = self.get_token_location();
+            self.diagnostic_report(INVALID_BYTE_SEQUENCE, format!("Invalid {} byte sequence \"{}\".", self.encoding_, bytes.concat()),
+                    loc);
         }
     }
 
-    fn get_peek_char(&mut self) -> char {
-        let mut buffer = [0; 1];
-        match self.file_.read_exact(&mut buffer) {
-            Err(_e) => self.eof_flag_ = true,
-            Ok(()) => buffer[0] = std::u8::MAX,
-        };
-        self.file_.seek(SeekFrom::Current(-1)).unwrap();
-        buffer[0].into()
+    /// Peek the character after the current one.
+    fn get_peek_char(&self) -> char {
+        self.get_peek_string(1).chars().next().unwrap_or(char::MAX)
+    }
+
+    /// Peek at most `count` characters after the current one, fewer are returned near the end of file.
+    fn get_peek_string(&self, count: usize) -> String {
+        let mut peek = String::new();
+        let mut offset = self.position_;
+
+        for _ in 0..count {
+            match self.decode_char(offset) {
+                None => break,
+                Some((decoded, length)) => {
+                    peek.push(decoded.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    offset += length;
+                },
+            }
+        }
+
+        peek
     }
 
     fn add_to_buffer(&mut self, ch: char) {
         self.buffer_.push(ch);
     }
 
-    fn reduce_buffer(&mut self) {
-        self.buffer_.pop();
+    fn error_report(&mut self, code: &'static str, msg: String) {
+        self.error_report_at(code, msg, self.loc_.to_owned());
     }
 
-    fn error_token(&mut self, msg: &String) {
-        eprintln!("Token Error:{}", msg);
+    /// Report the error which starts at `loc` rather than the start of token.
+    fn error_report_at(&mut self, code: &'static str, msg: String, loc: TokenLocation) {
+        self.diagnostic_report(code, msg, loc);
         self.error_flag_ = true;
     }
 
-    fn error_report(&mut self, msg: &String) {
-        self.error_token(&format!("Token Error: {}{}", self.get_token_location().to_string(), msg));
+    /// Only record the error, the current token is still produced.
+    fn diagnostic_report(&mut self, code: &'static str, msg: String, loc: TokenLocation) {
+        let diagnostic = Diagnostic::error(code, msg, self.get_span_location(loc));
+        self.diagnostics_.push(diagnostic);
     }
 
     fn handle_line_comment(&mut self) {
@@ -166,16 +445,22 @@ impl Scanner {
         }
     }
 
+    /// Check if the current character starts a doc comment, that is `/**` but not the empty comment `/**/`.
+    fn is_doc_comment_start(&self) -> bool {
+        self.current_char_ == '/' && self.get_peek_string(3).starts_with("**") && self.get_peek_string(3) != "**/"
+    }
+
     fn handle_block_comment(&mut self) {
         self.loc_ = self.get_token_location();
 
-        if self.current_char_ == '/' && self.get_peek_char() == '*' {
+        if self.current_char_ == '/' && self.get_peek_char() == '*' && !self.is_doc_comment_start() {
             self.get_next_char();
             self.get_next_char();
 
             while !(self.current_char_ == '*' && self.get_peek_char() == '/') {
                 if self.eof_flag_ {
-                    self.error_report(&format!("end of file happended in comment, */ is expected!, but find {}", self.current_char_));
+                    self.error_report(UNTERMINATED_COMMENT, "end of file happended in comment, */ is expected!".to_string());
+                    break;
                 }
 
                 self.get_next_char();
@@ -197,156 +482,325 @@ impl Scanner {
             self.handle_line_comment();
             self.handle_block_comment();
 
-            if !(self.current_char_.is_ascii_whitespace() || self.current_char_ == '/') || self.eof_flag_ {
+            // `/` may be the division operator rather than the start of comment, and doc comment is a token
+            let comment_start = self.current_char_ == '/' && (self.get_peek_char() == '/' || self.get_peek_char() == '*')
+                && !self.is_doc_comment_start();
+
+            if !(self.current_char_.is_ascii_whitespace() || comment_start) || self.eof_flag_ {
                 break;
             }
         }
     }
 
+    /// Get all diagnostics reported so far, in the order they were found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mjava_rust::Scanner;
+    ///
+    /// let mut scanner = Scanner::from_str("<memory>", "'ab'");
+    /// scanner.get_next_token();
+    /// assert_eq!(scanner.get_diagnostics()[0].get_code(), "E0004");
+    /// ```
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics_
+    }
+
+    /// Check if any `Severity::ERROR` diagnostic has been reported.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics_.iter().any(|diagnostic| diagnostic.is_error())
+    }
+
     /// Get the current token.
     ///
     /// # Examples
     ///
     /// ```
-    /// let scanner = Scanner::new("/test.mjava");
+    /// use mjava_rust::Scanner;
+    ///
+    /// let mut scanner = Scanner::from_str("<memory>", "class Main {}");
+    /// scanner.get_next_token();
     /// let token = scanner.get_token();
     /// ```
     pub fn get_token(&self) -> Token {
         self.token_.to_owned()
     }
 
-    /// Get the next token.
+    /// Get the next token, lexical errors are skipped and only reported by `get_diagnostics`.
     ///
     /// # Examples
     /// ```
-    /// let scanner = Scanner::new("./test.mjava");
+    /// use mjava_rust::Scanner;
+    ///
+    /// let mut scanner = Scanner::from_str("<memory>", "class Main {}");
     /// let token = scanner.get_next_token();
     /// ```
     pub fn get_next_token(&mut self) -> Token {
-        let mut matched;
-
         loop {
-            self.error_flag_ = false;
+            if let Ok(token) = self.scan_token() {
+                return token;
+            }
+        }
+    }
 
-            match self.state_ {
-                State::NONE => matched = false,
-                _ => matched = true,
+    /// Scan one token, the first diagnostic reported while scanning it is returned as the error.
+    fn scan_token(&mut self) -> Result<Token, LexError> {
+        if let Some(err) = self.pending_error_.take() {
+            return Err(err);
+        }
+
+        let diagnostic_count = self.diagnostics_.len();
+        self.error_flag_ = false;
+
+        if let State::NONE = self.state_ {
+            let trivia_offset = self.offset_;
+
+            self.preprocess();
+
+            if self.trivia_flag_ {
+                let trivia = self.get_source_text(trivia_offset, self.offset_);
+                self.leading_trivia_.push_str(&trivia);
             }
 
+            if self.eof_flag_ {
+                self.state_ = State::END_OF_FILE;
+            } else if self.current_char_.is_ascii_alphabetic() {
+                self.state_ = State::IDENTIFIER;
+            } else if self.current_char_.is_ascii_digit() || (self.current_char_ == '.' && self.get_peek_char().is_ascii_digit()) {
+                self.state_ = State::NUMBER;
+            } else if self.current_char_ == '\'' {
+                self.state_ = State::CHAR_LITERAL;
+            } else if self.current_char_ == '\"' {
+                self.state_ = State::STRING_LITERAL;
+            } else if self.is_doc_comment_start() {
+                self.state_ = State::DOC_COMMENT;
+            } else {
+                self.state_ = State::OPERATION;
+            }
+        }
+
+        let preprocess_error = self.error_flag_;
+
+        if !self.error_flag_ {
             match self.state_ {
-                State::NONE => self.get_next_char(),
+                State::NONE => {},
                 State::END_OF_FILE => self.handle_eof_state(),
                 State::IDENTIFIER => self.handle_identifier_state(),
                 State::NUMBER => self.handle_number_state(),
                 State::CHAR_LITERAL => self.handle_char_state(),
                 State::STRING_LITERAL => self.handle_string_state(),
                 State::OPERATION => self.handle_operation_state(),
+                State::DOC_COMMENT => self.handle_doc_comment_state(),
             }
+        }
 
-            match self.state_ {
-                State::NONE => {
-                    self.preprocess();
-
-                    if self.eof_flag_ {
-                        self.state_ = State::END_OF_FILE;
-                    } else {
-                        if self.current_char_.is_ascii_alphabetic() {
-                            self.state_ = State::IDENTIFIER;
-                        } else if self.current_char_.is_ascii_digit() {
-                            self.state_ = State::NUMBER;
-                        } else if self.current_char_ == '\'' {
-                            self.state_ = State::CHAR_LITERAL;
-                        } else if self.current_char_ == '\"' {
-                            self.state_ = State::STRING_LITERAL;
-                        } else {
-                            self.state_ = State::OPERATION;
-                        }
-                    }
-                },
-                _ => {},
+        if self.error_flag_ {
+            if self.trivia_flag_ && !preprocess_error {
+                let skipped = self.get_source_text(self.loc_.get_offset(), self.offset_);
+                self.leading_trivia_.push_str(&skipped);
             }
 
-            if matched && !self.error_flag_ {
-                break;
+            return Err(LexError::new(self.diagnostics_[diagnostic_count].to_owned()));
+        }
+
+        if self.trivia_flag_ {
+            self.handle_trivia();
+
+            if let TokenType::END_OF_FILE = self.token_.get_token_type() {
+                self.eof_trivia_.push_str(self.token_.get_leading_trivia());
+                self.eof_trivia_.push_str(self.token_.get_trailing_trivia());
             }
         }
 
-        self.token_.to_owned()
+        Ok(self.token_.to_owned())
     }
 
-    fn handle_eof_state(&mut self) {
-        self.loc_ = self.get_token_location();
-        self.make_token(TokenType::END_OF_FILE, TokenValue::UNRESERVED, self.loc_.to_owned(), "END_OF_FILE".to_string(), -1);
-    }
+    /// Attach the pending leading trivia and the trailing trivia up to the end of line to the current token.
+    ///
+    /// If there is nothing but trivia before the end of file, all of it is the trailing trivia.
+    fn handle_trivia(&mut self) {
+        let trivia_offset = self.offset_;
 
-    fn handle_digit(&mut self) {
-        self.add_to_buffer(self.current_char_);
-        self.get_next_char();
+        while !self.eof_flag_ {
+            if self.current_char_ == '\n' {
+                self.get_next_char();
+                break;
+            }
 
-        while self.current_char_.is_ascii_digit() {
-            self.add_to_buffer(self.current_char_);
-            self.get_next_char();
+            if self.current_char_.is_ascii_whitespace() {
+                self.get_next_char();
+            } else if self.current_char_ == '/' && self.get_peek_char() == '/' {
+                self.handle_line_comment();
+                break;
+            } else if self.current_char_ == '/' && self.get_peek_char() == '*' && !self.is_doc_comment_start()
+                && self.find_comment_end(self.offset_ + 2).is_some() {
+                self.handle_block_comment();
+            } else {
+                break;
+            }
         }
-    }
 
-    fn handle_xdigit(&mut self) {
-        let mut read_flag = false;
+        if self.is_trivia_to_end() {
+            let diagnostic_count = self.diagnostics_.len();
 
-        while self.current_char_.is_ascii_hexdigit() {
-            read_flag = true;
-            self.add_to_buffer(self.current_char_);
-            self.get_next_char();
+            self.preprocess();
+
+            // the unterminated comment is yielded as the error after this token
+            if self.error_flag_ {
+                self.error_flag_ = false;
+                self.pending_error_ = Some(LexError::new(self.diagnostics_[diagnostic_count].to_owned()));
+            }
         }
 
-        if !read_flag {
-            self.error_report(&"Hexadecimal number format error.".to_string());
+        let trailing_trivia = self.get_source_text(trivia_offset, self.offset_);
+        let leading_trivia = std::mem::take(&mut self.leading_trivia_);
+        self.token_.set_trivia(leading_trivia, trailing_trivia);
+    }
+
+    /// Check if there are only whitespace and comments from the current character to the end of file.
+    fn is_trivia_to_end(&self) -> bool {
+        let mut offset = self.offset_;
+
+        while offset < self.source_.len() {
+            let rest = &self.source_[offset..];
+
+            if rest[0].is_ascii_whitespace() {
+                offset += 1;
+            } else if rest.starts_with(b"//") {
+                match rest.iter().position(|byte| *byte == b'\n') {
+                    None => return true,
+                    Some(length) => offset += length + 1,
+                }
+            } else if rest.starts_with(b"/**") && !rest.starts_with(b"/**/") {
+                return false;
+            } else if rest.starts_with(b"/*") {
+                match self.find_comment_end(offset + 2) {
+                    None => return true,
+                    Some(end) => offset = end,
+                }
+            } else {
+                return false;
+            }
         }
+
+        true
     }
 
-    fn handle_odigit(&mut self) {
+    /// Find the byte offset after `*/`, which closes the block comment whose content starts at `offset`.
+    fn find_comment_end(&self, offset: usize) -> Option<usize> {
+        let content = self.source_.get(offset..)?;
+
+        content.windows(2).position(|window| window == b"*/").map(|position| offset + position + 2)
+    }
+
+    /// Get the decoded source code between byte offsets.
+    fn get_source_text(&self, offset: usize, end_offset: usize) -> String {
+        String::from_utf8_lossy(&self.source_[offset..end_offset]).into_owned()
+    }
+
+    fn handle_eof_state(&mut self) {
+        self.loc_ = self.get_token_location();
+        self.make_token(TokenType::END_OF_FILE, TokenValue::UNRESERVED, self.loc_.to_owned(), "END_OF_FILE", -1);
+    }
+
+    /// Read the digits of `radix`, which may be separated by underscores, only the digits are added to the buffer.
+    ///
+    /// Return whether any digit has been read.
+    fn handle_digits(&mut self, radix: u32) -> bool {
         let mut read_flag = false;
+        let mut underscore_loc = None;
+
+        while self.current_char_.is_digit(radix) || self.current_char_ == '_' {
+            if self.current_char_ != '_' {
+                read_flag = true;
+                underscore_loc = None;
+                self.add_to_buffer(self.current_char_);
+            } else if !read_flag {
+                let loc = self.get_token_location();
+                self.error_report_at(MALFORMED_NUMBER, "Underscores must be between digits of number literal.".to_string(), loc);
+            } else if underscore_loc.is_none() {
+                underscore_loc = Some(self.get_token_location());
+            }
 
-        while self.current_char_ >= '0' && self.current_char_ <= '7' {
-            read_flag = true;
-            self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
 
-        if !read_flag
-        {
-            self.error_report(&"Octal number format error.".to_string());
+        if let Some(loc) = underscore_loc {
+            self.error_report_at(MALFORMED_NUMBER, "Underscores must be between digits of number literal.".to_string(), loc);
         }
+
+        read_flag
     }
 
-    fn handle_fraction(&mut self) {
-        if !self.current_char_.is_ascii_digit() {
-            self.error_report(&"Fraction number part should be numbers".to_string());
+    fn handle_integer(&mut self, number_base: u32) {
+        let read_flag = self.handle_digits(number_base);
+
+        let base_name = match number_base {
+            16 => "Hexadecimal",
+            8 => "Octal",
+            2 => "Binary",
+            _ => return,
+        };
+
+        if !read_flag || (number_base != 16 && self.current_char_.is_ascii_digit()) {
+            self.error_report(MALFORMED_NUMBER, format!("{} number format error.", base_name));
+            self.handle_digits(10);
         }
+    }
 
+    /// Read the dot and the digits after it, which may be empty after the integer part, such as `1.` and `1.e5`.
+    fn handle_fraction(&mut self) {
         self.add_to_buffer(self.current_char_);
         self.get_next_char();
 
-        while self.current_char_.is_ascii_digit() {
-            self.add_to_buffer(self.current_char_);
-            self.get_next_char();
-        }
+        self.handle_digits(10);
     }
 
     fn handle_exponent(&mut self) {
         self.add_to_buffer(self.current_char_);
         self.get_next_char();
 
-        while self.current_char_ != '+' && self.current_char_ != '-' && !self.current_char_.is_ascii_digit() {
-            self.error_report(&format!("Scientist presentation number after e / E should be + / - or digits but find
-                        \'{}\'", self.current_char_));
-        }
-
         if self.current_char_ == '+' || self.current_char_ == '-' {
             self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
+
+        if !self.handle_digits(10) {
+            self.error_report(MALFORMED_NUMBER, format!("Scientist presentation number after e / E should be + / - or digits but find \'{}\'",
+                        self.current_char_));
+        }
     }
 
+    /// Read the suffix of number literal, such as `L` or `f`, and return the width of its value.
+    fn handle_number_suffix(&mut self, number_base: u32, is_float: bool) -> NumberWidth {
+        let suffix_loc = self.get_token_location();
+
+        let (number_width, suffix_flag) = match self.current_char_ {
+            'l' | 'L' if !is_float => (NumberWidth::I64, true),
+            'f' | 'F' if number_base == 10 => (NumberWidth::F32, true),
+            'd' | 'D' if number_base == 10 => (NumberWidth::F64, true),
+            _ if is_float => (NumberWidth::F64, false),
+            _ => (NumberWidth::I32, false),
+        };
+
+        if suffix_flag {
+            self.get_next_char();
+        }
+
+        // the rest of suffix is checked for real numbers too, such as `1.5L` and `3.14abc`
+
+        if self.current_char_.is_ascii_alphanumeric() || self.current_char_ == '_' {
+            while self.current_char_.is_ascii_alphanumeric() || self.current_char_ == '_' {
+                self.get_next_char();
+            }
+
+            let suffix = self.get_source_text(suffix_loc.get_offset(), self.offset_);
+            self.error_report_at(MALFORMED_NUMBER, format!("Invalid suffix \"{}\" of number literal.", suffix), suffix_loc);
+        }
+
+        number_width
+    }
 
     fn handle_number_state(&mut self) {
         self.loc_ = self.get_token_location();
@@ -361,14 +815,17 @@ impl Scanner {
 
             self.get_next_char();
             self.get_next_char();
-        }
-
-        if self.current_char_ == '0' && self.get_peek_char() >= '0' && self.get_peek_char() <= '7' {
-            number_base = 8;
+        } else if self.current_char_ == '0' && (self.get_peek_char() == 'b' || self.get_peek_char() == 'B') {
+            number_base = 2;
 
             self.get_next_char();
+            self.get_next_char();
+        } else if self.current_char_ == '0' && (self.get_peek_char().is_ascii_digit() || self.get_peek_char() == '_') {
+            // the leading zero is kept, so `0_7` is read as the digits of octal number
+            number_base = 8;
         }
 
+        #[allow(clippy::upper_case_acronyms)]
         enum NumberState {
             INTERGER,
             FRACTION,
@@ -380,15 +837,7 @@ impl Scanner {
 
         loop {
             match number_state {
-                NumberState::INTERGER => {
-                    if number_base == 10 {
-                        self.handle_digit();
-                    } else if number_base == 16 {
-                        self.handle_xdigit();
-                    } else if number_base == 8 {
-                        self.handle_odigit();
-                    }
-                },
+                NumberState::INTERGER => self.handle_integer(number_base),
                 NumberState::FRACTION => {
                     self.handle_fraction();
                     is_float = true;
@@ -402,25 +851,33 @@ impl Scanner {
 
             if self.current_char_ == '.' {
                 if is_float {
-                    self.error_report(&"Fraction number can not have more than one dot.".to_string());
+                    self.error_report(MALFORMED_NUMBER, "Fraction number can not have more than one dot.".to_string());
                 }
 
                 if is_exponent {
-                    self.error_report(&"Scientist number representation in MJava can not have dot.".to_string());
+                    self.error_report(MALFORMED_NUMBER, "Scientist number representation in MJava can not have dot.".to_string());
                 }
 
                 if number_base == 16 {
-                    self.error_report(&"Hexadecimal number in MJava can only be integer.".to_string());
+                    self.error_report(MALFORMED_NUMBER, "Hexadecimal number in MJava can only be integer.".to_string());
                 }
 
                 if number_base == 8 {
-                    self.error_report(&"Octal number in MJava can only be integer.".to_string());
+                    self.error_report(MALFORMED_NUMBER, "Octal number in MJava can only be integer.".to_string());
+                }
+
+                if number_base == 2 {
+                    self.error_report(MALFORMED_NUMBER, "Binary number in MJava can only be integer.".to_string());
                 }
 
                 number_state = NumberState::FRACTION;
-            } else if self.current_char_ == 'E' || self.current_char_ == 'e' {
+            } else if number_base != 16 && (self.current_char_ == 'E' || self.current_char_ == 'e') {
                 if is_exponent {
-                    self.error_report(&"Scientist presentation can not have more than one e / E".to_string());
+                    self.error_report(MALFORMED_NUMBER, "Scientist presentation can not have more than one e / E".to_string());
+                }
+
+                if number_base != 10 {
+                    self.error_report(MALFORMED_NUMBER, "Scientist presentation in MJava can only be decimal.".to_string());
                 }
 
                 number_state = NumberState::EXPONENT;
@@ -428,77 +885,226 @@ impl Scanner {
                 number_state = NumberState::DONE;
             }
 
-            match number_state {
-                NumberState::DONE => break,
-                _ => {},
+            if let NumberState::DONE = number_state {
+                break;
             }
         }
 
+        let number_width = self.handle_number_suffix(number_base, is_float || is_exponent);
+
         if !self.error_flag_ {
-            if is_float || is_exponent {
-                let real_value: f64 = match self.buffer_.parse::<f64>() {
+            let name = self.get_source_text(self.loc_.get_offset(), self.offset_);
+
+            if number_width.is_real() {
+                let real_value = match number_width {
+                    NumberWidth::F32 => self.buffer_.parse::<f32>().map(f64::from),
+                    _ => self.buffer_.parse::<f64>(),
+                };
+
+                let real_value: f64 = match real_value {
                     Err(err) => {
-                        self.error_report(&format!("When parse floating-point number literal \"{}\", because {}, an error
-                                    occurred.", self.buffer_, err.to_string()));
-                        self.buffer_.clear();
-                        self.state_ = State::NONE;
-                        std::f64::MAX
+                        self.error_report(MALFORMED_NUMBER, format!("When parse floating-point number literal \"{}\", because {}, an error occurred.",
+                                    name, err));
+                        f64::MAX
+                    },
+                    Ok(real_value) if real_value.is_infinite() => {
+                        self.error_report(NUMBER_OUT_OF_RANGE, format!("Floating-point number literal \"{}\" is too large for {}.", name,
+                                    number_width));
+                        f64::MAX
                     },
                     Ok(real_value) => real_value,
                 };
 
-                self.make_real_token(self.loc_.to_owned(), self.buffer_.to_owned(), real_value);
+                self.make_real_token(self.loc_.to_owned(), name, real_value, number_width);
             } else {
-                let int_value: i32 = match i32::from_str_radix(&self.buffer_.clone(), number_base) {
+                // the range of width is checked later by `Token::check_int_literal`, which knows whether it is negated
+                let int_value: u64 = match u64::from_str_radix(&self.buffer_, number_base) {
                     Err(err) => {
-                        self.error_report(&format!("When parse integer literal \"{}\", because {}, an error occurred.", self.buffer_,
-                                err.to_string()));
-                        self.buffer_.clear();
-                        self.state_ = State::NONE;
-                        std::i32::MAX
+                        self.error_report(NUMBER_OUT_OF_RANGE, format!("When parse integer literal \"{}\", because {}, an error occurred.", name,
+                                err));
+                        u64::MAX
                     },
                     Ok(int_value) => int_value,
                 };
 
-                self.make_int_token(self.loc_.to_owned(), self.buffer_.to_owned(), int_value);
+                self.make_int_token(self.loc_.to_owned(), name, int_value, number_width);
             }
-        } else {
+        }
+
+        if self.error_flag_ {
             self.buffer_.clear();
             self.state_ = State::NONE;
         }
     }
 
-    fn handle_char_state(&mut self) {
-        self.loc_ = self.get_token_location();
+    /// Read the quoted literal, the raw spelling with quotes is kept in the buffer, and the decoded value is returned.
+    fn handle_quoted(&mut self, quote: char) -> String {
+        let mut value = String::new();
 
+        self.add_to_buffer(self.current_char_);
         self.get_next_char();
 
         loop {
-            if self.current_char_ != '\\' && self.get_peek_char() == '\'' {
-                self.add_to_buffer(self.current_char_);
+            if self.eof_flag_ || self.current_char_ == '\n' {
+                let (code, kind) = if quote == '\'' {
+                    (UNTERMINATED_CHAR, "char")
+                } else {
+                    (UNTERMINATED_STRING, "string")
+                };
+
+                let end = if self.eof_flag_ { "file" } else { "line" };
+                self.error_report(code, format!("end of {} happened in {}, {} is expected!", end, kind, quote));
                 break;
             }
 
-            if self.eof_flag_ {
-                self.error_report(&format!("end of file happended in string, \' is expected!, but find {}",
-                            self.current_char_));
+            if self.current_char_ == quote {
+                self.add_to_buffer(self.current_char_);
+                self.get_next_char();
                 break;
             }
 
+            if self.current_char_ == '\\' {
+                if let Some(ch) = self.handle_escape() {
+                    value.push(ch);
+                }
+            } else {
+                value.push(self.current_char_);
+                self.add_to_buffer(self.current_char_);
+                self.get_next_char();
+            }
+        }
+
+        value
+    }
+
+    /// Decode the escape sequence which starts at the current `\\`.
+    ///
+    /// Return `None` if the escape sequence is invalid, which has already been reported.
+    fn handle_escape(&mut self) -> Option<char> {
+        let loc = self.get_token_location();
+
+        self.add_to_buffer(self.current_char_);
+        self.get_next_char();
+
+        let ch = match self.current_char_ {
+            'n' => '\n',
+            't' => '\t',
+            'b' => '\u{8}',
+            'r' => '\r',
+            'f' => '\u{c}',
+            '\\' => '\\',
+            '\'' => '\'',
+            '\"' => '\"',
+            '0'..='7' => return self.handle_octal_escape(loc),
+            'u' => return self.handle_unicode_escape(loc),
+            _ => {
+                let msg = if self.eof_flag_ || self.current_char_ == '\n' {
+                    "Escape sequence is incomplete.".to_string()
+                } else {
+                    format!("Invalid escape sequence \"\\{}\", valid ones are \\b \\t \\n \\f \\r \\\" \\\' \\\\, octal and \\uXXXX.",
+                            self.current_char_)
+                };
+
+                self.error_report_at(INVALID_ESCAPE, msg, loc);
+                return None;
+            },
+        };
+
+        self.add_to_buffer(self.current_char_);
+        self.get_next_char();
+        Some(ch)
+    }
+
+    /// `\\0` to `\\377`, at most three digits if the first one is `0` to `3`, otherwise at most two.
+    fn handle_octal_escape(&mut self, loc: TokenLocation) -> Option<char> {
+        let max_length = if self.current_char_ <= '3' { 3 } else { 2 };
+        let mut value = 0;
+        let mut length = 0;
+
+        while length < max_length && ('0'..='7').contains(&self.current_char_) {
+            value = value * 8 + self.current_char_.to_digit(8).unwrap();
+            length += 1;
             self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
 
-        if !self.eof_flag_ {
+        if std::char::from_u32(value).is_none() {
+            self.error_report_at(INVALID_ESCAPE, format!("Octal escape sequence \\{:o} is out of range.", value), loc);
+        }
+
+        std::char::from_u32(value)
+    }
+
+    /// `\\uXXXX`, where `u` can be repeated, and a surrogate pair is combined into one character.
+    fn handle_unicode_escape(&mut self, loc: TokenLocation) -> Option<char> {
+        let high = self.handle_utf16_unit(&loc)?;
+
+        if !(0xD800..0xDC00).contains(&high) {
+            return match std::char::from_u32(high) {
+                None => {
+                    self.error_report_at(INVALID_ESCAPE, format!("Unicode escape sequence \\u{:04X} is a lone surrogate.", high), loc);
+                    None
+                },
+                ch => ch,
+            };
+        }
+
+        if self.current_char_ == '\\' && self.get_peek_char() == 'u' {
+            let low_loc = self.get_token_location();
+
+            self.add_to_buffer(self.current_char_);
             self.get_next_char();
+
+            let low = self.handle_utf16_unit(&low_loc)?;
+
+            if (0xDC00..0xE000).contains(&low) {
+                return std::char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+            }
+        }
+
+        self.error_report_at(INVALID_ESCAPE, format!("Unicode escape sequence \\u{:04X} is a lone surrogate.", high), loc);
+        None
+    }
+
+    /// Read `u` and four hexadecimal digits of the unicode escape sequence.
+    fn handle_utf16_unit(&mut self, loc: &TokenLocation) -> Option<u32> {
+        while self.current_char_ == 'u' {
+            self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
 
-        if !self.error_flag_ && self.buffer_.len() == 1 {
-            let ch = self.buffer_.chars().next().unwrap();
-            self.make_char_token(self.loc_.to_owned(), self.buffer_.clone(), ch);
+        let mut value = 0;
+
+        for _ in 0..4 {
+            match self.current_char_.to_digit(16) {
+                None => {
+                    self.error_report_at(INVALID_ESCAPE, "Unicode escape sequence should have four hexadecimal digits.".to_string(),
+                            loc.to_owned());
+                    return None;
+                },
+                Some(digit) => value = value * 16 + digit,
+            }
+
+            self.add_to_buffer(self.current_char_);
+            self.get_next_char();
+        }
+
+        Some(value)
+    }
+
+    fn handle_char_state(&mut self) {
+        self.loc_ = self.get_token_location();
+
+        let value = self.handle_quoted('\'');
+
+        if self.error_flag_ {
+            self.buffer_.clear();
+            self.state_ = State::NONE;
+        } else if value.chars().count() == 1 {
+            let ch = value.chars().next().unwrap();
+            self.make_char_token(self.loc_.to_owned(), self.buffer_.to_owned(), ch);
         } else {
-            self.error_report(&"Char can contain only one character!".to_string());
+            self.error_report(INVALID_CHAR_LITERAL, "Char can contain only one character!".to_string());
             self.buffer_.clear();
             self.state_ = State::NONE;
         }
@@ -507,35 +1113,55 @@ impl Scanner {
     fn handle_string_state(&mut self) {
         self.loc_ = self.get_token_location();
 
-        self.get_next_char();
+        let value = self.handle_quoted('\"');
 
-        loop {
-            if self.eof_flag_ {
-                self.error_report(&format!("end of file happended in string, \" is expected!, but find {}",
-                            self.current_char_));
-                break;
-            }
+        if !self.error_flag_ {
+            self.make_str_token(self.loc_.to_owned(), self.buffer_.to_owned(), value);
+        } else {
+            self.buffer_.clear();
+            self.state_ = State::NONE;
+        }
+    }
 
-            if self.current_char_ != '\\' && self.get_peek_char() == '\"' {
-                self.add_to_buffer(self.current_char_);
-                break;
-            }
+    fn handle_doc_comment_state(&mut self) {
+        self.loc_ = self.get_token_location();
 
+        // skip `/**`
+        for _ in 0..3 {
             self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
 
-        if !self.eof_flag_ {
-            self.get_next_char();
+        let mut content = String::new();
+
+        while !(self.current_char_ == '*' && self.get_peek_char() == '/') {
+            if self.eof_flag_ {
+                self.error_report(UNTERMINATED_COMMENT, "end of file happened in doc comment, */ is expected!".to_string());
+                self.buffer_.clear();
+                self.state_ = State::NONE;
+                return;
+            }
+
+            content.push(self.current_char_);
+            self.add_to_buffer(self.current_char_);
             self.get_next_char();
         }
 
-        if !self.error_flag_ {
-            self.make_str_token(self.loc_.to_owned(), self.buffer_.clone(), self.buffer_.clone());
-        } else {
-            self.buffer_.clear();
-            self.state_ = State::NONE;
+        for _ in 0..2 {
+            self.add_to_buffer(self.current_char_);
+            self.get_next_char();
         }
+
+        // remove the leading `*` of lines, and blank lines around the text
+        let lines: Vec<&str> = content.lines().map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        }).collect();
+
+        let text = lines.join("\n").trim_matches('\n').to_string();
+
+        self.make_doc_comment_token(self.loc_.to_owned(), self.buffer_.to_owned(), text);
     }
 
     fn handle_identifier_state(&mut self) {
@@ -549,44 +1175,305 @@ impl Scanner {
             self.get_next_char();
         }
 
-        let copy = self.buffer_.clone();
-
-        if self.buffer_.eq("System") {
-            let mut length = 12;
-
-            while length > 0 && !self.eof_flag_ {
-                self.add_to_buffer(self.current_char_);
-                self.get_next_char();
-                length = length - 1;
-            }
-
-            if !self.buffer_.eq("System.out.println") {
-                self.buffer_ = copy;
-                self.file_.seek(SeekFrom::Current(-length)).unwrap();
-            }
-        }
-
         let (token_value, token_type, precedence) = self.dictionary_.lookup(&self.buffer_);
 
-        self.make_token(token_type, token_value, self.loc_.to_owned(), self.buffer_.to_owned(), precedence);
+        self.make_token(token_type, token_value, self.loc_.to_owned(), &self.buffer_.to_owned(), precedence);
     }
 
+    /// Find the longest operator or delimiter in dictionary, which starts with the current character.
+    ///
+    /// If there is no such one, the current character is made into a `TokenType::UNKNOWN` token.
     fn handle_operation_state(&mut self) {
         self.loc_ = self.get_token_location();
 
-        self.add_to_buffer(self.current_char_);
+        let mut candidate = self.get_peek_string(self.dictionary_.get_max_symbol_length().saturating_sub(1));
+        candidate.insert(0, self.current_char_);
 
-        let ch = self.get_peek_char();
-        self.add_to_buffer(ch);
+        while candidate.chars().count() > 1 && !self.dictionary_.have_token(&candidate) {
+            candidate.pop();
+        }
+
+        if !self.dictionary_.have_token(&candidate) {
+            self.handle_unknown_state();
+            return;
+        }
 
-        if self.dictionary_.have_token(&self.buffer_) {
+        for _ in 0..candidate.chars().count() {
+            self.add_to_buffer(self.current_char_);
             self.get_next_char();
-        } else {
-            self.reduce_buffer();
         }
 
         let (token_value, token_type, precedence) = self.dictionary_.lookup(&self.buffer_);
-        self.make_token(token_type, token_value, self.loc_.to_owned(), self.buffer_.to_owned(), precedence);
+        self.make_token(token_type, token_value, self.loc_.to_owned(), &self.buffer_.to_owned(), precedence);
+    }
+
+    fn handle_unknown_state(&mut self) {
+        // invalid bytes have already been reported
+        if !self.replaced_flag_ {
+            self.diagnostic_report(UNKNOWN_CHARACTER, format!("Unknown character \'{}\' (U+{:04X}).", self.current_char_,
+                        self.current_char_ as u32), self.loc_.to_owned());
+        }
+
+        self.add_to_buffer(self.current_char_);
         self.get_next_char();
+        self.make_token(TokenType::UNKNOWN, TokenValue::UNRESERVED, self.loc_.to_owned(), &self.buffer_.to_owned(), -1);
+    }
+}
+
+impl Iterator for Scanner {
+    type Item = Result<Token, LexError>;
+
+    /// Get the next token or lexical error, `None` is returned at the end of file.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.scan_token() {
+            Ok(token) => match token.get_token_type() {
+                TokenType::END_OF_FILE => None,
+                _ => Some(Ok(token)),
+            },
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scan source, and return the tokens with the codes of diagnostics.
+    fn scan(source: &str) -> (Vec<Token>, Vec<&'static str>) {
+        let mut scanner = Scanner::from_str("<test>", source);
+        let tokens = scanner.by_ref().filter_map(Result::ok).collect();
+        let codes = scanner.get_diagnostics().iter().map(Diagnostic::get_code).collect();
+        (tokens, codes)
+    }
+
+    fn scan_str_value(source: &str) -> String {
+        let (tokens, codes) = scan(source);
+        assert!(codes.is_empty(), "{:?} for {}", codes, source);
+        tokens[0].get_str_value().to_string()
+    }
+
+    #[test]
+    fn simple_escapes_are_decoded() {
+        assert_eq!(scan_str_value(r#""a\tb\n\r\b\f\\\"\'""#), "a\tb\n\r\u{8}\u{c}\\\"\'");
+
+        let (tokens, codes) = scan(r"'\n'");
+        assert!(codes.is_empty());
+        assert_eq!(tokens[0].get_char_value(), '\n');
+    }
+
+    #[test]
+    fn octal_escapes_take_at_most_three_digits() {
+        assert_eq!(scan_str_value(r#""\0\7\101\377""#), "\0\u{7}A\u{ff}");
+        // `\400` is out of range, so it is `\40` followed by `0`
+        assert_eq!(scan_str_value(r#""\400""#), " 0");
+        assert_eq!(scan_str_value(r#""\1018""#), "A8");
+    }
+
+    #[test]
+    fn unicode_escapes_are_decoded() {
+        assert_eq!(scan_str_value(r#""A\uuu00e9""#), "A\u{e9}");
+
+        let (tokens, codes) = scan(r"'\u0041'");
+        assert!(codes.is_empty());
+        assert_eq!(tokens[0].get_char_value(), 'A');
+    }
+
+    #[test]
+    fn surrogate_pairs_are_combined() {
+        assert_eq!(scan_str_value(r#""\uD83D\uDE00""#), "\u{1F600}");
+        assert_eq!(scan_str_value(r#""\uD83D\uuDE00!""#), "\u{1F600}!");
+    }
+
+    #[test]
+    fn invalid_escapes_are_reported() {
+        for source in [r#""\q""#, r#""\u00G1""#, r#""\uD83D""#, r#""\uD83Dx""#, r#""\uDE00""#, r#""\uD83DA""#] {
+            let (_, codes) = scan(source);
+            assert_eq!(codes, [INVALID_ESCAPE], "{}", source);
+        }
+    }
+
+    #[test]
+    fn number_suffixes_decide_the_width() {
+        let cases = [
+            ("10", NumberWidth::I32), ("10L", NumberWidth::I64), ("0x1Fl", NumberWidth::I64), ("0b101L", NumberWidth::I64),
+            ("1.5", NumberWidth::F64), ("1.5f", NumberWidth::F32), ("2F", NumberWidth::F32), ("2d", NumberWidth::F64),
+            ("1e5", NumberWidth::F64), ("1e-5f", NumberWidth::F32),
+        ];
+
+        for (source, number_width) in cases {
+            let (tokens, codes) = scan(source);
+            assert!(codes.is_empty(), "{:?} for {}", codes, source);
+            assert_eq!(tokens[0].get_number_width(), number_width, "{}", source);
+        }
+    }
+
+    #[test]
+    fn number_values_are_decoded() {
+        let (tokens, codes) = scan("0x1F 017 0b101 1_000 0_7 1__0");
+        assert!(codes.is_empty());
+        let values: Vec<_> = tokens.iter().map(Token::get_int_value).collect();
+        assert_eq!(values, [31, 15, 5, 1000, 7, 10]);
+
+        let (tokens, codes) = scan("1_0.2_5e1_0");
+        assert!(codes.is_empty());
+        assert_eq!(tokens[0].get_real_value(), 10.25e10);
+    }
+
+    #[test]
+    fn invalid_number_suffixes_are_reported() {
+        for source in ["1.5L", "3.14abc", "1e5L", "10x", "0x1Fz", "0b1f", "10L_", "2d5"] {
+            let (_, codes) = scan(source);
+            assert_eq!(codes, [MALFORMED_NUMBER], "{}", source);
+        }
+    }
+
+    #[test]
+    fn misplaced_underscores_are_reported() {
+        for source in ["1_", "0x_1", "0b1_", "1_.5", "1._5", "1.5_", "1e_5", "1_L"] {
+            let (_, codes) = scan(source);
+            assert_eq!(codes, [MALFORMED_NUMBER], "{}", source);
+        }
+    }
+
+    #[test]
+    fn fraction_or_integer_part_can_be_empty() {
+        let cases = [("1.", 1.0), ("1.e5", 1e5), ("1.f", 1.0), (".5", 0.5), (".5e1", 5.0), ("0.", 0.0)];
+
+        for (source, value) in cases {
+            let (tokens, codes) = scan(source);
+            assert!(codes.is_empty(), "{:?} for {}", codes, source);
+            assert_eq!(tokens.len(), 1, "{}", source);
+            assert_eq!(tokens[0].get_token_type(), TokenType::REAL_LITERAL, "{}", source);
+            assert_eq!(tokens[0].get_real_value(), value, "{}", source);
+        }
+
+        assert_eq!(scan_names("a.b", Dialect::MJAVA), ["a", ".", "b"]);
+        assert_eq!(scan_names("a[0].length", Dialect::MJAVA), ["a", "[", "0", "]", ".", "length"]);
+        assert_eq!(scan_names("x=.5;", Dialect::MJAVA), ["x", "=", ".5", ";"]);
+    }
+
+    /// Scan source with trivia kept, and join the tokens with their trivia and the trivia at the end of file.
+    fn round_trip(source: &str) -> String {
+        let mut scanner = Scanner::from_str("<test>", source);
+        scanner.set_trivia(true);
+
+        let mut text = String::new();
+
+        for token in scanner.by_ref().filter_map(Result::ok) {
+            text.push_str(token.get_leading_trivia());
+            text.push_str(token.get_name());
+            text.push_str(token.get_trailing_trivia());
+        }
+
+        text.push_str(scanner.get_eof_trivia());
+        text
+    }
+
+    #[test]
+    fn trivia_round_trips_comment_only_source() {
+        for source in ["", "  \n\n", "// only comment", "// only comment\n", "/* c */", " /* a\n * b\n */ \n// c\n"] {
+            assert_eq!(round_trip(source), source);
+        }
+    }
+
+    #[test]
+    fn trivia_round_trips_source_with_tokens() {
+        for source in ["int a; // count\n", "\n/* c */ int a;\n\n// end", "/** doc */\nclass A {}\n", "int a; \"abc"] {
+            assert_eq!(round_trip(source), source);
+        }
+    }
+
+    fn scan_names(source: &str, dialect: Dialect) -> Vec<String> {
+        let mut scanner = Scanner::from_str("<test>", source);
+        scanner.set_dialect(dialect);
+        scanner.filter_map(Result::ok).map(|token| token.get_name().to_string()).collect()
+    }
+
+    #[test]
+    fn operators_take_the_longest_match() {
+        let cases: [(&str, &[&str]); 7] = [
+            ("x>>>=>>=", &["x", ">>>=", ">>="]),
+            ("a>>>b", &["a", ">>>", "b"]),
+            ("i+++j", &["i", "++", "+", "j"]),
+            ("a-->b", &["a", "--", ">", "b"]),
+            ("a/b", &["a", "/", "b"]),
+            ("a/**/b", &["a", "b"]),
+            ("a/=b", &["a", "/=", "b"]),
+        ];
+
+        for (source, names) in cases {
+            assert_eq!(scan_names(source, Dialect::MJAVA), names, "{}", source);
+        }
+    }
+
+    #[test]
+    fn java_separators_are_unknown_in_mjava() {
+        assert_eq!(scan_names("a->b", Dialect::MJAVA), ["a", "-", ">", "b"]);
+        assert_eq!(scan_names("a->b", Dialect::JAVA_SUBSET), ["a", "->", "b"]);
+        assert_eq!(scan_names("f(int... a)", Dialect::JAVA_SUBSET), ["f", "(", "int", "...", "a", ")"]);
+
+        let (tokens, codes) = scan("a @ b");
+        assert_eq!(tokens[1].get_token_type(), TokenType::UNKNOWN);
+        assert_eq!(codes, [UNKNOWN_CHARACTER]);
+    }
+
+    fn scan_types(source: &str, dialect: Dialect) -> Vec<TokenType> {
+        let mut scanner = Scanner::from_str("<test>", source);
+        scanner.set_dialect(dialect);
+        scanner.filter_map(Result::ok).map(|token| token.get_token_type()).collect()
+    }
+
+    #[test]
+    fn dialects_decide_the_reserved_words() {
+        use TokenType::*;
+
+        let source = "long String main for null";
+        assert_eq!(scan_types(source, Dialect::MINIJAVA), [IDENTIFIER, TYPE, KEYWORD, IDENTIFIER, IDENTIFIER]);
+        assert_eq!(scan_types(source, Dialect::MJAVA), [TYPE, TYPE, KEYWORD, KEYWORD, IDENTIFIER]);
+        assert_eq!(scan_types(source, Dialect::JAVA_SUBSET), [TYPE, IDENTIFIER, IDENTIFIER, KEYWORD, KEYWORD]);
+    }
+
+    #[test]
+    fn dialects_decide_the_operators() {
+        assert_eq!(scan_names("i += 1 >> 2", Dialect::MINIJAVA), ["i", "+", "=", "1", ">", ">", "2"]);
+        assert_eq!(scan_names("i += 1 >> 2", Dialect::MJAVA), ["i", "+=", "1", ">>", "2"]);
+        assert_eq!(scan_names("i += 1 >> 2", Dialect::JAVA_SUBSET), ["i", "+=", "1", ">>", "2"]);
+    }
+
+    #[test]
+    fn long_and_float_are_types_with_their_literals() {
+        for dialect in [Dialect::MJAVA, Dialect::JAVA_SUBSET] {
+            let mut scanner = Scanner::from_str("<test>", "long x = 10L; float y = 3.14f;");
+            scanner.set_dialect(dialect);
+            let tokens: Vec<_> = scanner.filter_map(Result::ok).collect();
+
+            assert_eq!(tokens[0].get_token_value(), TokenValue::LONG);
+            assert_eq!(tokens[3].get_number_width(), NumberWidth::I64);
+            assert_eq!(tokens[5].get_token_value(), TokenValue::FLOAT);
+            assert_eq!(tokens[8].get_number_width(), NumberWidth::F32);
+        }
+    }
+
+    #[test]
+    fn truncated_utf8_in_a_string_is_reported() {
+        let source: &[u8] = b"s = \"ab\xe4\xb8\";";
+        let mut scanner = Scanner::from_reader_with_encoding("<test>", source, SourceEncoding::UTF8).unwrap();
+        let tokens: Vec<_> = scanner.by_ref().filter_map(Result::ok).collect();
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].get_code(), INVALID_BYTE_SEQUENCE);
+        assert_eq!((diagnostics[0].get_location().get_line(), diagnostics[0].get_location().get_column()), (1, 8));
+        assert_eq!(tokens[2].get_str_value(), "ab\u{FFFD}");
+        assert_eq!(tokens[3].get_location().get_column(), 10);
+    }
+
+    #[test]
+    fn columns_count_characters_after_chinese_text() {
+        let (tokens, codes) = scan("s = \"中文\" + x; // 注释\ny");
+        assert!(codes.is_empty());
+        let columns: Vec<_> = tokens.iter().map(|token| (token.get_location().get_line(), token.get_location().get_column())).collect();
+        assert_eq!(columns, [(1, 1), (1, 3), (1, 5), (1, 10), (1, 12), (1, 13), (2, 1)]);
     }
 }
@@ -0,0 +1,162 @@
+//! Global symbol table which interns names and file names.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+/// The number of strings in the first chunk of `STRINGS`, each of the next chunks is twice as large as the previous one
+const FIRST_CHUNK_SIZE: usize = 1024;
+/// The number of chunks, which is enough for every `u32` index
+const CHUNK_COUNT: usize = 23;
+
+/// The interned strings indexed by symbol, which is only appended to, so that reading it does not lock.
+///
+/// The chunks never move once they are allocated, and every string is set once before its symbol is handed out.
+static STRINGS: [OnceLock<Box<[OnceLock<&'static str>]>>; CHUNK_COUNT] = [const { OnceLock::new() }; CHUNK_COUNT];
+
+/// Get the slot of string at `index` in `STRINGS`, the chunk is allocated if it does not exist.
+fn get_string_slot(index: usize) -> &'static OnceLock<&'static str> {
+    let chunk = (index / FIRST_CHUNK_SIZE + 1).ilog2() as usize;
+    let offset = index - FIRST_CHUNK_SIZE * ((1 << chunk) - 1);
+    let strings = STRINGS[chunk].get_or_init(|| (0..FIRST_CHUNK_SIZE << chunk).map(|_| OnceLock::new()).collect());
+    &strings[offset]
+}
+
+/// Table which maps strings to their symbols, the strings are leaked so that they live as long as the program.
+///
+/// Only names, such as identifiers, reserved words and file names, are interned, whose number is limited
+/// by the vocabulary of programs, so the leaked memory does not grow without bound.
+struct Interner {
+    symbols_: HashMap<&'static str, Symbol>,
+}
+
+impl Interner {
+    fn new() -> Self {
+        let mut interner = Interner {
+            symbols_: HashMap::new(),
+        };
+
+        interner.intern("");
+        interner
+    }
+
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols_.get(name) {
+            return *symbol;
+        }
+
+        let index = self.symbols_.len();
+        let symbol = Symbol(u32::try_from(index).expect("too many symbols are interned"));
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+
+        let _ = get_string_slot(index).set(name);
+        self.symbols_.insert(name, symbol);
+        symbol
+    }
+}
+
+fn get_interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(Interner::new()))
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+/// Interned string in the global symbol table, which is cheap to copy and compare.
+///
+/// The symbol is the index of string in the table, and reading the string does not lock the table.
+/// The default one is the empty string.
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Intern `name` into the global symbol table.
+    ///
+    /// # Examples
+    /// ```
+    /// use mjava_rust::Symbol;
+    ///
+    /// let symbol = Symbol::intern("count");
+    /// assert_eq!(symbol, Symbol::intern("count"));
+    /// assert_eq!(symbol.as_str(), "count");
+    /// assert_eq!(Symbol::default().as_str(), "");
+    /// ```
+    pub fn intern(name: &str) -> Symbol {
+        get_interner().lock().unwrap_or_else(|err| err.into_inner()).intern(name)
+    }
+
+    /// Get the interned string.
+    pub fn as_str(self) -> &'static str {
+        // only the default symbol can be read before anything is interned
+        get_string_slot(self.0 as usize).get().copied().unwrap_or_default()
+    }
+
+    /// Get the index in the global symbol table.
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Symbol({:?})", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+/// The id of source file, whose name is interned in the global symbol table
+pub struct FileId(Symbol);
+
+impl FileId {
+    /// Get the id of source file by its name, the same name always gets the same id.
+    pub fn new(file_name: &str) -> FileId {
+        FileId(Symbol::intern(file_name))
+    }
+
+    /// Get the name of source file.
+    pub fn get_file_name(self) -> &'static str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Debug for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FileId({:?})", self.get_file_name())
+    }
+}
+
+impl fmt::Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_file_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_are_indexes() {
+        assert_eq!(std::mem::size_of::<Symbol>(), 4);
+        assert_eq!(std::mem::size_of::<FileId>(), 4);
+        assert_eq!(Symbol::intern(""), Symbol::default());
+    }
+
+    #[test]
+    fn strings_are_read_across_chunks() {
+        // more than the first two chunks
+        let names: Vec<String> = (0..4 * FIRST_CHUNK_SIZE).map(|index| format!("symbol_test_{}", index)).collect();
+        let symbols: Vec<Symbol> = names.iter().map(|name| Symbol::intern(name)).collect();
+
+        for (name, symbol) in names.iter().zip(&symbols) {
+            assert_eq!(symbol.as_str(), name);
+            assert_eq!(Symbol::intern(name), *symbol);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Syntax helpers over tokens, such as qualified names.
+
+use crate::token::*;
+use std::fmt;
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// The well-known qualified name, which is scanned as identifiers and dots, and recognized by syntax
+pub enum WellKnownName {
+    /// `System.out.println`
+    PRINTLN,
+    /// `System.out.print`
+    PRINT,
+    /// `System.exit`
+    EXIT,
+}
+
+impl WellKnownName {
+    /// Look up the well-known name by its parts, such as `["System", "out", "println"]`.
+    pub fn lookup(parts: &[String]) -> Option<Self> {
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+
+        match parts.as_slice() {
+            ["System", "out", "println"] => Some(WellKnownName::PRINTLN),
+            ["System", "out", "print"] => Some(WellKnownName::PRINT),
+            ["System", "exit"] => Some(WellKnownName::EXIT),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WellKnownName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WellKnownName::PRINTLN => write!(f, "System.out.println"),
+            WellKnownName::PRINT => write!(f, "System.out.print"),
+            WellKnownName::EXIT => write!(f, "System.exit"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Qualified name, which is made up of names separated by `.`, such as `System.out.println`
+pub struct QualifiedName {
+    parts_: Vec<String>,
+    location_: TokenLocation,
+}
+
+impl QualifiedName {
+    /// Parse the longest qualified name at the start of tokens, and return it with the number of tokens it takes.
+    ///
+    /// Whitespace and comments are not tokens, so `System . out /* */ . println` is the same as `System.out.println`.
+    pub fn parse(tokens: &[Token]) -> Option<(QualifiedName, usize)> {
+        let first = tokens.first().filter(|token| QualifiedName::is_part(token))?;
+
+        let mut name = QualifiedName {
+            parts_: vec![first.get_name().to_string()],
+            location_: first.get_location().to_owned(),
+        };
+
+        let mut count = 1;
+
+        while let (Some(dot), Some(part)) = (tokens.get(count), tokens.get(count + 1)) {
+            if !matches!(dot.get_token_value(), TokenValue::DOT) || !QualifiedName::is_part(part) {
+                break;
+            }
+
+            let loc = part.get_location();
+            name.parts_.push(part.get_name().to_string());
+            name.location_ = name.location_.extend_to(loc.get_end_line(), loc.get_end_column(), loc.get_end_offset());
+            count += 2;
+        }
+
+        Some((name, count))
+    }
+
+    /// Check if token can be part of qualified name, which is an identifier, or a keyword used as name in MJava.
+    fn is_part(token: &Token) -> bool {
+        matches!(token.get_token_type(), TokenType::IDENTIFIER) || matches!(token.get_token_value(), TokenValue::MAIN | TokenValue::LENGTH)
+    }
+
+    /// Get the names separated by `.`, such as `["System", "out", "println"]`.
+    pub fn get_parts(&self) -> &[String] {
+        &self.parts_
+    }
+
+    /// Get the location from the first name to the last one.
+    pub fn get_location(&self) -> &TokenLocation {
+        &self.location_
+    }
+
+    /// Get the well-known name it is, such as `WellKnownName::PRINTLN` for `System.out.println`.
+    pub fn get_well_known_name(&self) -> Option<WellKnownName> {
+        WellKnownName::lookup(&self.parts_)
+    }
+}
+
+impl fmt::Display for QualifiedName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.parts_.join("."))
+    }
+}
+
+/// Find the calls of well-known names in tokens, such as `System.out.println(`.
+///
+/// The qualified name should not follow `.`, so `a.System.out.println(` is not one of them.
+pub fn find_well_known_calls(tokens: &[Token]) -> Vec<(WellKnownName, QualifiedName)> {
+    let tokens: Vec<Token> = tokens.iter().filter(|token| !matches!(token.get_token_type(), TokenType::DOC_COMMENT)).cloned().collect();
+    let mut calls = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let after_dot = index > 0 && matches!(tokens[index - 1].get_token_value(), TokenValue::DOT);
+
+        match QualifiedName::parse(&tokens[index..]) {
+            Some((name, count)) => {
+                let is_call = tokens.get(index + count).is_some_and(|token| matches!(token.get_token_value(), TokenValue::LPAREN));
+
+                if let Some(well_known_name) = name.get_well_known_name().filter(|_| is_call && !after_dot) {
+                    calls.push((well_known_name, name));
+                }
+
+                index += count;
+            },
+            None => index += 1,
+        }
+    }
+
+    calls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn scan(source: &str) -> Vec<Token> {
+        Scanner::from_str("<test>", source).filter_map(Result::ok).collect()
+    }
+
+    fn find_calls(source: &str) -> Vec<(WellKnownName, String)> {
+        find_well_known_calls(&scan(source)).into_iter().map(|(name, qualified_name)| (name, qualified_name.to_string())).collect()
+    }
+
+    #[test]
+    fn qualified_names_take_the_longest_parts() {
+        let tokens = scan("System . out . println(x)");
+        let (name, count) = QualifiedName::parse(&tokens).unwrap();
+        assert_eq!(name.get_parts(), ["System", "out", "println"]);
+        assert_eq!(count, 5);
+        assert_eq!(name.get_well_known_name(), Some(WellKnownName::PRINTLN));
+
+        let loc = name.get_location();
+        assert_eq!((loc.get_column(), loc.get_end_column()), (1, 22));
+    }
+
+    #[test]
+    fn comments_between_parts_are_ignored() {
+        let tokens = scan("System./* c */out // c\n.println");
+        let (name, count) = QualifiedName::parse(&tokens).unwrap();
+        assert_eq!(name.to_string(), "System.out.println");
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn qualified_names_stop_at_the_end_of_file() {
+        let (name, count) = QualifiedName::parse(&scan("System")).unwrap();
+        assert_eq!((name.to_string(), count), ("System".to_string(), 1));
+        assert_eq!(name.get_well_known_name(), None);
+
+        let (name, count) = QualifiedName::parse(&scan("System.out.")).unwrap();
+        assert_eq!((name.to_string(), count), ("System.out".to_string(), 3));
+
+        assert!(QualifiedName::parse(&scan("")).is_none());
+        assert!(QualifiedName::parse(&scan(".System")).is_none());
+    }
+
+    #[test]
+    fn well_known_calls_are_found() {
+        let source = "System.out.println(1); System . out . print(2); System.exit(0);";
+        assert_eq!(find_calls(source), [
+            (WellKnownName::PRINTLN, "System.out.println".to_string()),
+            (WellKnownName::PRINT, "System.out.print".to_string()),
+            (WellKnownName::EXIT, "System.exit".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn names_which_are_not_calls_are_skipped() {
+        assert!(find_calls("a.System.out.println(1);").is_empty());
+        assert!(find_calls("System.out.println;").is_empty());
+        assert!(find_calls("System.out.println").is_empty());
+        assert!(find_calls("System.out.printf(1);").is_empty());
+        assert_eq!(find_calls("/** doc */ System.out.println(1);").len(), 1);
+    }
+}
@@ -1,6 +1,15 @@
-#[allow(dead_code)]
+//! Tokens produced by the scanner, with their types, values, locations and literals.
+
+use crate::diagnostic::*;
+use crate::symbol::*;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 /// The type of token
 pub enum TokenType {
     /// such as `3`, `4` and so on
@@ -19,46 +28,148 @@ pub enum TokenType {
     KEYWORD,
     /// such as `int`
     TYPE,
-    /// such as `+`, `-`, `*`, `<`, `&&`
+    /// such as `+`, `<=`, `&&`, `>>>=`
     OPERATOR,
     /// such as `,`, `;`
     DELIMITER,
+    /// such as `/** The entry of program. */`
+    DOC_COMMENT,
     /// end of file
     END_OF_FILE,
     /// other unknown token type
     UNKNOWN,
 }
 
-#[allow(dead_code)]
-#[derive(Copy, Clone)]
+impl FromStr for TokenType {
+    type Err = String;
+
+    /// Parse the name of variant, such as `INTEGER_LITERAL`, case is ignored.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_uppercase().as_str() {
+            "INTEGER_LITERAL" => Ok(TokenType::INTEGER_LITERAL),
+            "BOOLEAN_LITERAL" => Ok(TokenType::BOOLEAN_LITERAL),
+            "REAL_LITERAL" => Ok(TokenType::REAL_LITERAL),
+            "CHAR_LITERAL" => Ok(TokenType::CHAR_LITERAL),
+            "STRING_LITERAL" => Ok(TokenType::STRING_LITERAL),
+            "IDENTIFIER" => Ok(TokenType::IDENTIFIER),
+            "KEYWORD" => Ok(TokenType::KEYWORD),
+            "TYPE" => Ok(TokenType::TYPE),
+            "OPERATOR" => Ok(TokenType::OPERATOR),
+            "DELIMITER" => Ok(TokenType::DELIMITER),
+            "DOC_COMMENT" => Ok(TokenType::DOC_COMMENT),
+            "END_OF_FILE" => Ok(TokenType::END_OF_FILE),
+            "UNKNOWN" => Ok(TokenType::UNKNOWN),
+            _ => Err(format!("unknown token type \"{}\"", name)),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 /// The value of token
 pub enum TokenValue {
-    /// keyword
+    /// keywords
+    /// 'class'
     CLASS = 0,
+    /// 'public'
     PUBLIC,
+    /// 'static'
     STATIC,
+    /// 'void'
     VOID,
+    /// 'main'
     MAIN,
+    /// 'extends'
     EXTENDS,
+    /// 'return'
     RETURN,
+    /// 'if'
     IF,
+    /// 'else'
     ELSE,
+    /// 'while'
     WHILE,
+    /// 'for'
     FOR,
+    /// `System.out.println`, which is no longer produced by the scanner
+    #[deprecated(note = "`System.out.println` is scanned as identifiers and dots, see `syntax::WellKnownName`")]
     PRINT,
+    /// 'length'
     LENGTH,
+    /// 'this'
     THIS,
+    /// 'new'
     NEW,
+    /// 'private'
+    PRIVATE,
+    /// 'protected'
+    PROTECTED,
+    /// 'final'
+    FINAL,
+    /// 'abstract'
+    ABSTRACT,
+    /// 'interface'
+    INTERFACE,
+    /// 'implements'
+    IMPLEMENTS,
+    /// 'import'
+    IMPORT,
+    /// 'package'
+    PACKAGE,
+    /// 'super'
+    SUPER,
+    /// 'null'
+    NULL,
+    /// 'instanceof'
+    INSTANCEOF,
+    /// 'break'
+    BREAK,
+    /// 'continue'
+    CONTINUE,
+    /// 'do'
+    DO,
+    /// 'switch'
+    SWITCH,
+    /// 'case'
+    CASE,
+    /// 'default'
+    DEFAULT,
+    /// 'try'
+    TRY,
+    /// 'catch'
+    CATCH,
+    /// 'finally'
+    FINALLY,
+    /// 'throw'
+    THROW,
+    /// 'throws'
+    THROWS,
 
-    /// type
+    /// types
+    /// 'double'
     DOUBLE,
+    /// 'int'
     INT,
+    /// 'char'
     CHAR,
+    /// 'String'
     STRING,
+    /// 'boolean'
     BOOL,
+    /// 'byte'
+    BYTE,
+    /// 'short'
+    SHORT,
+    /// 'long'
+    LONG,
+    /// 'float'
+    FLOAT,
 
-    /// boolean
+    /// booleans
+    /// 'true'
     TRUE,
+    /// 'false'
     FALSE,
 
     /// symbols
@@ -68,7 +179,7 @@ pub enum TokenValue {
     RPAREN,
     /// '['
     LBRACK,
-    /// '['
+    /// ']'
     RBRACK,
     /// '{'
     LBRACE,
@@ -78,18 +189,86 @@ pub enum TokenValue {
     COMMA,
     /// ';'
     SEMICOLON,
+    /// '::'
+    DOUBLE_COLON,
+    /// '...'
+    ELLIPSIS,
+    /// '@'
+    AT,
     /// '='
     ASSIGN,
+    /// '+='
+    ADD_ASSIGN,
+    /// '-='
+    SUB_ASSIGN,
+    /// '*='
+    MULTI_ASSIGN,
+    /// '/='
+    DIV_ASSIGN,
+    /// '%='
+    MOD_ASSIGN,
+    /// '&='
+    BIT_AND_ASSIGN,
+    /// '|='
+    BIT_OR_ASSIGN,
+    /// '^='
+    BIT_XOR_ASSIGN,
+    /// '<<='
+    SHL_ASSIGN,
+    /// '>>='
+    SHR_ASSIGN,
+    /// '>>>='
+    USHR_ASSIGN,
+    /// '?'
+    QUESTION,
+    /// ':'
+    COLON,
+    /// '->'
+    ARROW,
+    /// '||'
+    OR,
     /// '&&'
     AND,
+    /// '|'
+    BIT_OR,
+    /// '^'
+    BIT_XOR,
+    /// '&'
+    BIT_AND,
+    /// '=='
+    EQ,
+    /// '!='
+    NE,
     /// '<'
     LT,
-    /// '-'
+    /// '>'
+    GT,
+    /// '<='
+    LE,
+    /// '>='
+    GE,
+    /// '<<'
+    SHL,
+    /// '>>'
+    SHR,
+    /// '>>>'
+    USHR,
+    /// '+'
     ADD,
     /// '-'
     SUB,
     /// '*'
     MULTI,
+    /// '/'
+    DIV,
+    /// '%'
+    MOD,
+    /// '++'
+    INC,
+    /// '--'
+    DEC,
+    /// '~'
+    BIT_NOT,
     /// '.'
     DOT,
     /// '!'
@@ -98,61 +277,437 @@ pub enum TokenValue {
     UNRESERVED,
 }
 
+impl FromStr for TokenValue {
+    type Err = String;
+
+    /// Parse the name of variant, such as `CLASS`, case is ignored.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_uppercase().as_str() {
+            "CLASS" => Ok(TokenValue::CLASS),
+            "PUBLIC" => Ok(TokenValue::PUBLIC),
+            "STATIC" => Ok(TokenValue::STATIC),
+            "VOID" => Ok(TokenValue::VOID),
+            "MAIN" => Ok(TokenValue::MAIN),
+            "EXTENDS" => Ok(TokenValue::EXTENDS),
+            "RETURN" => Ok(TokenValue::RETURN),
+            "IF" => Ok(TokenValue::IF),
+            "ELSE" => Ok(TokenValue::ELSE),
+            "WHILE" => Ok(TokenValue::WHILE),
+            "FOR" => Ok(TokenValue::FOR),
+            #[allow(deprecated)]
+            "PRINT" => Ok(TokenValue::PRINT),
+            "LENGTH" => Ok(TokenValue::LENGTH),
+            "THIS" => Ok(TokenValue::THIS),
+            "NEW" => Ok(TokenValue::NEW),
+            "PRIVATE" => Ok(TokenValue::PRIVATE),
+            "PROTECTED" => Ok(TokenValue::PROTECTED),
+            "FINAL" => Ok(TokenValue::FINAL),
+            "ABSTRACT" => Ok(TokenValue::ABSTRACT),
+            "INTERFACE" => Ok(TokenValue::INTERFACE),
+            "IMPLEMENTS" => Ok(TokenValue::IMPLEMENTS),
+            "IMPORT" => Ok(TokenValue::IMPORT),
+            "PACKAGE" => Ok(TokenValue::PACKAGE),
+            "SUPER" => Ok(TokenValue::SUPER),
+            "NULL" => Ok(TokenValue::NULL),
+            "INSTANCEOF" => Ok(TokenValue::INSTANCEOF),
+            "BREAK" => Ok(TokenValue::BREAK),
+            "CONTINUE" => Ok(TokenValue::CONTINUE),
+            "DO" => Ok(TokenValue::DO),
+            "SWITCH" => Ok(TokenValue::SWITCH),
+            "CASE" => Ok(TokenValue::CASE),
+            "DEFAULT" => Ok(TokenValue::DEFAULT),
+            "TRY" => Ok(TokenValue::TRY),
+            "CATCH" => Ok(TokenValue::CATCH),
+            "FINALLY" => Ok(TokenValue::FINALLY),
+            "THROW" => Ok(TokenValue::THROW),
+            "THROWS" => Ok(TokenValue::THROWS),
+            "DOUBLE" => Ok(TokenValue::DOUBLE),
+            "INT" => Ok(TokenValue::INT),
+            "CHAR" => Ok(TokenValue::CHAR),
+            "STRING" => Ok(TokenValue::STRING),
+            "BOOL" => Ok(TokenValue::BOOL),
+            "BYTE" => Ok(TokenValue::BYTE),
+            "SHORT" => Ok(TokenValue::SHORT),
+            "LONG" => Ok(TokenValue::LONG),
+            "FLOAT" => Ok(TokenValue::FLOAT),
+            "TRUE" => Ok(TokenValue::TRUE),
+            "FALSE" => Ok(TokenValue::FALSE),
+            "LPAREN" => Ok(TokenValue::LPAREN),
+            "RPAREN" => Ok(TokenValue::RPAREN),
+            "LBRACK" => Ok(TokenValue::LBRACK),
+            "RBRACK" => Ok(TokenValue::RBRACK),
+            "LBRACE" => Ok(TokenValue::LBRACE),
+            "RBRACE" => Ok(TokenValue::RBRACE),
+            "COMMA" => Ok(TokenValue::COMMA),
+            "SEMICOLON" => Ok(TokenValue::SEMICOLON),
+            "DOUBLE_COLON" => Ok(TokenValue::DOUBLE_COLON),
+            "ELLIPSIS" => Ok(TokenValue::ELLIPSIS),
+            "AT" => Ok(TokenValue::AT),
+            "ASSIGN" => Ok(TokenValue::ASSIGN),
+            "ADD_ASSIGN" => Ok(TokenValue::ADD_ASSIGN),
+            "SUB_ASSIGN" => Ok(TokenValue::SUB_ASSIGN),
+            "MULTI_ASSIGN" => Ok(TokenValue::MULTI_ASSIGN),
+            "DIV_ASSIGN" => Ok(TokenValue::DIV_ASSIGN),
+            "MOD_ASSIGN" => Ok(TokenValue::MOD_ASSIGN),
+            "BIT_AND_ASSIGN" => Ok(TokenValue::BIT_AND_ASSIGN),
+            "BIT_OR_ASSIGN" => Ok(TokenValue::BIT_OR_ASSIGN),
+            "BIT_XOR_ASSIGN" => Ok(TokenValue::BIT_XOR_ASSIGN),
+            "SHL_ASSIGN" => Ok(TokenValue::SHL_ASSIGN),
+            "SHR_ASSIGN" => Ok(TokenValue::SHR_ASSIGN),
+            "USHR_ASSIGN" => Ok(TokenValue::USHR_ASSIGN),
+            "QUESTION" => Ok(TokenValue::QUESTION),
+            "COLON" => Ok(TokenValue::COLON),
+            "ARROW" => Ok(TokenValue::ARROW),
+            "OR" => Ok(TokenValue::OR),
+            "AND" => Ok(TokenValue::AND),
+            "BIT_OR" => Ok(TokenValue::BIT_OR),
+            "BIT_XOR" => Ok(TokenValue::BIT_XOR),
+            "BIT_AND" => Ok(TokenValue::BIT_AND),
+            "EQ" => Ok(TokenValue::EQ),
+            "NE" => Ok(TokenValue::NE),
+            "LT" => Ok(TokenValue::LT),
+            "GT" => Ok(TokenValue::GT),
+            "LE" => Ok(TokenValue::LE),
+            "GE" => Ok(TokenValue::GE),
+            "SHL" => Ok(TokenValue::SHL),
+            "SHR" => Ok(TokenValue::SHR),
+            "USHR" => Ok(TokenValue::USHR),
+            "ADD" => Ok(TokenValue::ADD),
+            "SUB" => Ok(TokenValue::SUB),
+            "MULTI" => Ok(TokenValue::MULTI),
+            "DIV" => Ok(TokenValue::DIV),
+            "MOD" => Ok(TokenValue::MOD),
+            "INC" => Ok(TokenValue::INC),
+            "DEC" => Ok(TokenValue::DEC),
+            "BIT_NOT" => Ok(TokenValue::BIT_NOT),
+            "DOT" => Ok(TokenValue::DOT),
+            "NOT" => Ok(TokenValue::NOT),
+            "UNRESERVED" => Ok(TokenValue::UNRESERVED),
+            _ => Err(format!("unknown token value \"{}\"", name)),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+/// The width of number literal value, which is decided by its suffix
+pub enum NumberWidth {
+    /// integer without suffix, such as `10`
+    #[default]
+    I32,
+    /// integer with `L` or `l`, such as `10L`
+    I64,
+    /// floating-point number with `F` or `f`, such as `3.14f`
+    F32,
+    /// floating-point number without suffix or with `D` or `d`, such as `2.0d`
+    F64,
+}
+
+impl NumberWidth {
+    /// Whether it is the width of `REAL_LITERAL`.
+    pub fn is_real(self) -> bool {
+        matches!(self, NumberWidth::F32 | NumberWidth::F64)
+    }
+}
+
+impl fmt::Display for NumberWidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberWidth::I32 => write!(f, "int"),
+            NumberWidth::I64 => write!(f, "long"),
+            NumberWidth::F32 => write!(f, "float"),
+            NumberWidth::F64 => write!(f, "double"),
+        }
+    }
+}
+
 #[derive(Default)]
-#[derive(Clone)]
-/// The location of token
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// The location of token, which spans from the start to the end of it.
+///
+/// Lines and columns start from 1, and the end ones are those of the last character.
+/// Byte offsets start from 0, and the end one is exclusive, so `&source[offset..end_offset]` is the token,
+/// where `source` is the decoded source code got by `Scanner::get_source`, rather than the bytes of source file.
 pub struct TokenLocation {
-    file_name_: String,
+    file_id_: FileId,
     line_: i32,
-    column_: i32
+    column_: i32,
+    offset_: usize,
+    end_line_: i32,
+    end_column_: i32,
+    end_offset_: usize,
 }
 
 impl TokenLocation {
-    pub fn new(file_name: String, line: i32, column: i32) -> Self {
+    /// New location of one character, which starts at byte `offset` and takes `length` bytes.
+    pub fn new(file_id: FileId, line: i32, column: i32, offset: usize, length: usize) -> Self {
         TokenLocation {
-            file_name_: file_name,
+            file_id_: file_id,
             line_: line,
             column_: column,
+            offset_: offset,
+            end_line_: line,
+            end_column_: column,
+            end_offset_: offset + length,
+        }
+    }
+
+    /// Extend the location to the character at `end_line` and `end_column`, which ends at byte `end_offset`.
+    ///
+    /// Nothing changes if that character is before the end of location.
+    pub fn extend_to(mut self, end_line: i32, end_column: i32, end_offset: usize) -> Self {
+        if end_offset > self.end_offset_ {
+            self.end_line_ = end_line;
+            self.end_column_ = end_column;
+            self.end_offset_ = end_offset;
+        }
+
+        self
+    }
+
+    /// Get the id of source file.
+    pub fn get_file_id(&self) -> FileId {
+        self.file_id_
+    }
+
+    /// Get the name of source file.
+    pub fn get_file_name(&self) -> &'static str {
+        self.file_id_.get_file_name()
+    }
+
+    /// Get the line where the location starts.
+    pub fn get_line(&self) -> i32 {
+        self.line_
+    }
+
+    /// Get the column where the location starts.
+    pub fn get_column(&self) -> i32 {
+        self.column_
+    }
+
+    /// Get the byte offset where the location starts.
+    pub fn get_offset(&self) -> usize {
+        self.offset_
+    }
+
+    /// Get the line of the last character.
+    pub fn get_end_line(&self) -> i32 {
+        self.end_line_
+    }
+
+    /// Get the column of the last character.
+    pub fn get_end_column(&self) -> i32 {
+        self.end_column_
+    }
+
+    /// Get the byte offset after the location ends.
+    pub fn get_end_offset(&self) -> usize {
+        self.end_offset_
+    }
+}
+
+impl fmt::Display for TokenLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}:", self.file_id_, self.line_, self.column_)
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+/// The kind of token, which is `TokenType` together with its `TokenValue`
+pub enum TokenKind {
+    /// `TokenType::INTEGER_LITERAL`
+    INTEGER_LITERAL,
+    /// `TokenType::BOOLEAN_LITERAL` with `TokenValue::TRUE` or `TokenValue::FALSE`
+    BOOLEAN_LITERAL(TokenValue),
+    /// `TokenType::REAL_LITERAL`
+    REAL_LITERAL,
+    /// `TokenType::CHAR_LITERAL`
+    CHAR_LITERAL,
+    /// `TokenType::STRING_LITERAL`
+    STRING_LITERAL,
+    /// `TokenType::IDENTIFIER`
+    IDENTIFIER,
+    /// `TokenType::KEYWORD` with its value
+    KEYWORD(TokenValue),
+    /// `TokenType::TYPE` with its value
+    TYPE(TokenValue),
+    /// `TokenType::OPERATOR` with its value and precedence
+    OPERATOR(TokenValue, i32),
+    /// `TokenType::DELIMITER` with its value
+    DELIMITER(TokenValue),
+    /// `TokenType::DOC_COMMENT`
+    DOC_COMMENT,
+    /// `TokenType::END_OF_FILE`
+    END_OF_FILE,
+    /// `TokenType::UNKNOWN`
+    UNKNOWN,
+}
+
+impl TokenKind {
+    /// New kind from the type, value and precedence, which are kept only for those reserved in dictionary.
+    pub fn new(token_type: TokenType, token_value: TokenValue, symbol_precedence: i32) -> Self {
+        match token_type {
+            TokenType::INTEGER_LITERAL => TokenKind::INTEGER_LITERAL,
+            TokenType::BOOLEAN_LITERAL => TokenKind::BOOLEAN_LITERAL(token_value),
+            TokenType::REAL_LITERAL => TokenKind::REAL_LITERAL,
+            TokenType::CHAR_LITERAL => TokenKind::CHAR_LITERAL,
+            TokenType::STRING_LITERAL => TokenKind::STRING_LITERAL,
+            TokenType::IDENTIFIER => TokenKind::IDENTIFIER,
+            TokenType::KEYWORD => TokenKind::KEYWORD(token_value),
+            TokenType::TYPE => TokenKind::TYPE(token_value),
+            TokenType::OPERATOR => TokenKind::OPERATOR(token_value, symbol_precedence),
+            TokenType::DELIMITER => TokenKind::DELIMITER(token_value),
+            TokenType::DOC_COMMENT => TokenKind::DOC_COMMENT,
+            TokenType::END_OF_FILE => TokenKind::END_OF_FILE,
+            TokenType::UNKNOWN => TokenKind::UNKNOWN,
+        }
+    }
+
+    /// Get the type of token.
+    pub fn get_token_type(self) -> TokenType {
+        match self {
+            TokenKind::INTEGER_LITERAL => TokenType::INTEGER_LITERAL,
+            TokenKind::BOOLEAN_LITERAL(_) => TokenType::BOOLEAN_LITERAL,
+            TokenKind::REAL_LITERAL => TokenType::REAL_LITERAL,
+            TokenKind::CHAR_LITERAL => TokenType::CHAR_LITERAL,
+            TokenKind::STRING_LITERAL => TokenType::STRING_LITERAL,
+            TokenKind::IDENTIFIER => TokenType::IDENTIFIER,
+            TokenKind::KEYWORD(_) => TokenType::KEYWORD,
+            TokenKind::TYPE(_) => TokenType::TYPE,
+            TokenKind::OPERATOR(_, _) => TokenType::OPERATOR,
+            TokenKind::DELIMITER(_) => TokenType::DELIMITER,
+            TokenKind::DOC_COMMENT => TokenType::DOC_COMMENT,
+            TokenKind::END_OF_FILE => TokenType::END_OF_FILE,
+            TokenKind::UNKNOWN => TokenType::UNKNOWN,
+        }
+    }
+
+    /// Get the value, which is `TokenValue::UNRESERVED` unless the token is reserved in dictionary.
+    pub fn get_token_value(self) -> TokenValue {
+        match self {
+            TokenKind::BOOLEAN_LITERAL(token_value)
+            | TokenKind::KEYWORD(token_value)
+            | TokenKind::TYPE(token_value)
+            | TokenKind::OPERATOR(token_value, _)
+            | TokenKind::DELIMITER(token_value) => token_value,
+            _ => TokenValue::UNRESERVED,
+        }
+    }
+
+    /// Get the precedence of operator, which is `-1` for the others.
+    pub fn get_symbol_precedence(self) -> i32 {
+        match self {
+            TokenKind::OPERATOR(_, symbol_precedence) => symbol_precedence,
+            _ => -1,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug)]
+/// The value carried by literal or doc comment token
+pub enum Literal {
+    /// the unsigned value as written, whose range is checked by `Token::check_int_literal`
+    INTEGER(u64, NumberWidth),
+    /// the value of `float` literal is widened
+    REAL(f64, NumberWidth),
+    /// `true` or `false`
+    BOOLEAN(bool),
+    /// the decoded value of char literal
+    CHAR(char),
+    /// the decoded value of string literal
+    STRING(Box<str>),
+    /// the text of doc comment
+    DOC_COMMENT(Box<str>),
+}
+
+// real values are compared by bits, so that `Literal` can be `Eq` and `Hash`
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::INTEGER(value, width), Literal::INTEGER(other_value, other_width)) => value == other_value && width == other_width,
+            (Literal::REAL(value, width), Literal::REAL(other_value, other_width)) => {
+                value.to_bits() == other_value.to_bits() && width == other_width
+            },
+            (Literal::BOOLEAN(value), Literal::BOOLEAN(other_value)) => value == other_value,
+            (Literal::CHAR(value), Literal::CHAR(other_value)) => value == other_value,
+            (Literal::STRING(value), Literal::STRING(other_value)) => value == other_value,
+            (Literal::DOC_COMMENT(value), Literal::DOC_COMMENT(other_value)) => value == other_value,
+            _ => false,
         }
     }
+}
+
+impl Eq for Literal {}
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
 
-    pub fn to_string(&self) -> String {
-        format!("{}:{}:{}:", self.file_name_, self.line_, self.column_)
+        match self {
+            Literal::INTEGER(value, width) => (value, width).hash(state),
+            Literal::REAL(value, width) => (value.to_bits(), width).hash(state),
+            Literal::BOOLEAN(value) => value.hash(state),
+            Literal::CHAR(value) => value.hash(state),
+            Literal::STRING(value) | Literal::DOC_COMMENT(value) => value.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    /// Print the decoded value, strings and chars are quoted and escaped.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::INTEGER(value, width) => write!(f, "{} ({})", value, width),
+            Literal::REAL(value, width) => write!(f, "{:?} ({})", value, width),
+            Literal::BOOLEAN(value) => write!(f, "{}", value),
+            Literal::CHAR(value) => write!(f, "{:?}", value),
+            Literal::STRING(value) | Literal::DOC_COMMENT(value) => write!(f, "{:?}", value),
+        }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// The spelling of token which is owned rather than interned, such as that of literal, together with the value of literal
+struct TokenText {
+    name_: Box<str>,
+    literal_: Option<Literal>,
+}
+
+/// The value of `true`, whose name is interned like reserved words
+static TRUE_LITERAL: Literal = Literal::BOOLEAN(true);
+/// The value of `false`, whose name is interned like reserved words
+static FALSE_LITERAL: Literal = Literal::BOOLEAN(false);
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 /// Lexical token
 pub struct Token {
-    type_: TokenType,
-    value_: TokenValue,
+    kind_: TokenKind,
     location_: TokenLocation,
-    name_: String,
-    symbol_precedence_: i32,
-
-    int_value_: i32,
-    real_value_: f64,
-    char_value_: char,
-    str_value_: String
+    // the interned name, which is the empty one if the token has text
+    symbol_: Symbol,
+    // the spelling and value of literals, doc comments and the other tokens whose names are not interned
+    text_: Option<Box<TokenText>>,
+    // leading and trailing trivia, which are rarely kept
+    trivia_: Option<Box<(String, String)>>,
 }
 
 impl Default for Token {
     fn default() -> Self {
         Token {
-            type_: TokenType::IDENTIFIER,
-            value_: TokenValue::UNRESERVED,
+            kind_: TokenKind::IDENTIFIER,
             location_: Default::default(),
-            name_: Default::default(),
-            symbol_precedence_: -1,
-            int_value_: Default::default(),
-            real_value_: Default::default(),
-            char_value_: Default::default(),
-            str_value_: Default::default(),
+            symbol_: Symbol::default(),
+            text_: None,
+            trivia_: None,
         }
     }
 }
 
-#[allow(dead_code)]
 impl Token {
     /// Default constructor
     pub fn new() -> Self {
@@ -162,72 +717,235 @@ impl Token {
     }
 
     /// New one `IDENTIFIER` or `KEYWORD` token.
-    pub fn new_token(token_type: TokenType, token_value: TokenValue, loc: TokenLocation, name: String, symbol_precedence: i32) -> Self {
-        Token {
-            type_: token_type,
-            value_: token_value,
-            location_: loc,
-            name_: name,
-            symbol_precedence_: symbol_precedence,
-            ..Default::default()
+    ///
+    /// The names of identifiers and reserved words are interned, those of `UNKNOWN` and `END_OF_FILE` are not.
+    pub fn new_token(token_type: TokenType, token_value: TokenValue, loc: TokenLocation, name: &str, symbol_precedence: i32) -> Self {
+        let kind = TokenKind::new(token_type, token_value, symbol_precedence);
+
+        match token_type {
+            TokenType::UNKNOWN | TokenType::END_OF_FILE => Token::new_text_token(kind, loc, name.to_string(), None),
+            _ => Token {
+                kind_: kind,
+                location_: loc,
+                symbol_: Symbol::intern(name),
+                ..Default::default()
+            },
         }
     }
 
-    /// New one `INTEGER_LITERAL` token.
-    pub fn new_int_token(loc: TokenLocation, name: String, int_value: i32) -> Self {
-        Token {
-            type_: TokenType::INTEGER_LITERAL,
-            location_: loc,
-            name_: name,
-            int_value_: int_value,
-            ..Default::default()
-        }
+    /// New one `INTEGER_LITERAL` token, `int_value` is the unsigned value as written, whose range is not checked yet.
+    pub fn new_int_token(loc: TokenLocation, name: String, int_value: u64, number_width: NumberWidth) -> Self {
+        Token::new_text_token(TokenKind::INTEGER_LITERAL, loc, name, Some(Literal::INTEGER(int_value, number_width)))
     }
 
     /// New one`REAL_LITERAL` token.
-    pub fn new_real_token(loc: TokenLocation, name: String, real_value: f64) -> Self {
-        Token {
-            type_: TokenType::REAL_LITERAL,
-            location_: loc,
-            name_: name,
-            real_value_: real_value,
-            ..Default::default()
-        }
+    pub fn new_real_token(loc: TokenLocation, name: String, real_value: f64, number_width: NumberWidth) -> Self {
+        Token::new_text_token(TokenKind::REAL_LITERAL, loc, name, Some(Literal::REAL(real_value, number_width)))
     }
 
     /// New one `CHAR_LITERAL` token.
     pub fn new_char_token(loc: TokenLocation, name: String, char_value: char) -> Self {
-        Token {
-            type_: TokenType::CHAR_LITERAL,
-            location_: loc,
-            name_: name,
-            char_value_: char_value,
-            ..Default::default()
-        }
+        Token::new_text_token(TokenKind::CHAR_LITERAL, loc, name, Some(Literal::CHAR(char_value)))
     }
 
     /// New one `STRING_LITERAL` token.
     pub fn new_str_token(loc: TokenLocation, name: String, str_value: String) -> Self {
+        Token::new_text_token(TokenKind::STRING_LITERAL, loc, name, Some(Literal::STRING(str_value.into_boxed_str())))
+    }
+
+    /// New one `DOC_COMMENT` token, `text` is the content without `/**`, `*/` and the leading `*` of lines.
+    pub fn new_doc_comment_token(loc: TokenLocation, name: String, text: String) -> Self {
+        Token::new_text_token(TokenKind::DOC_COMMENT, loc, name, Some(Literal::DOC_COMMENT(text.into_boxed_str())))
+    }
+
+    fn new_text_token(kind: TokenKind, loc: TokenLocation, name: String, literal: Option<Literal>) -> Self {
         Token {
-            type_: TokenType::STRING_LITERAL,
+            kind_: kind,
             location_: loc,
-            name_: name,
-            str_value_: str_value,
+            text_: Some(Box::new(TokenText {
+                name_: name.into_boxed_str(),
+                literal_: literal,
+            })),
             ..Default::default()
         }
     }
 
+    /// Get the kind, which can be matched together with its value, such as `TokenKind::KEYWORD(TokenValue::CLASS)`.
+    pub fn get_kind(&self) -> TokenKind {
+        self.kind_
+    }
+
+    /// Get the type of token.
     pub fn get_token_type(&self) -> TokenType {
-        self.type_
+        self.kind_.get_token_type()
+    }
+
+    /// Get the value, which is `TokenValue::UNRESERVED` unless the token is reserved in dictionary.
+    pub fn get_token_value(&self) -> TokenValue {
+        self.kind_.get_token_value()
+    }
+
+    /// Get the precedence of operator, which is `-1` for the others.
+    pub fn get_symbol_precedence(&self) -> i32 {
+        self.kind_.get_symbol_precedence()
+    }
+
+    /// Get the value of literal or doc comment, which is `None` for the others.
+    pub fn get_literal(&self) -> Option<&Literal> {
+        match self.kind_ {
+            TokenKind::BOOLEAN_LITERAL(TokenValue::TRUE) => Some(&TRUE_LITERAL),
+            TokenKind::BOOLEAN_LITERAL(_) => Some(&FALSE_LITERAL),
+            _ => self.text_.as_ref().and_then(|text| text.literal_.as_ref()),
+        }
+    }
+
+    /// Get the location from the start to the end of token.
+    pub fn get_location(&self) -> &TokenLocation {
+        &self.location_
+    }
+
+    /// Get the unsigned value of `INTEGER_LITERAL` as written, see `check_int_literal` for the value in its width.
+    pub fn get_int_value(&self) -> u64 {
+        match self.get_literal() {
+            Some(Literal::INTEGER(int_value, _)) => *int_value,
+            _ => 0,
+        }
+    }
+
+    /// Check the range of `INTEGER_LITERAL` by Java semantics, and return its value, which is negated if `negated`.
+    ///
+    /// Decimal literal can exceed the maximum by one only as the operand of unary `-`, such as `-2147483648`,
+    /// while hexadecimal, octal and binary literals can take all bits as two's complement, such as `0xFFFFFFFF` for `-1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `NUMBER_OUT_OF_RANGE` diagnostic if the literal can not be represented in its width.
+    pub fn check_int_literal(&self, negated: bool) -> Result<i64, Diagnostic> {
+        let int_value = self.get_int_value();
+        let number_width = self.get_number_width();
+
+        let (max_value, max_bits) = match number_width {
+            NumberWidth::I64 => (i64::MAX as u64, u64::MAX),
+            _ => (i32::MAX as u64, u32::MAX as u64),
+        };
+
+        let in_range = if self.get_int_radix() == 10 {
+            int_value <= max_value || (negated && int_value == max_value + 1)
+        } else {
+            int_value <= max_bits
+        };
+
+        if !in_range {
+            return Err(Diagnostic::error(NUMBER_OUT_OF_RANGE, format!("Integer literal \"{}{}\" is out of the range of {}.",
+                        if negated { "-" } else { "" }, self.get_name(), number_width), self.location_.to_owned()));
+        }
+
+        let value = match number_width {
+            NumberWidth::I64 => {
+                let value = int_value as i64;
+                if negated { value.wrapping_neg() } else { value }
+            },
+            _ => {
+                let value = int_value as u32 as i32;
+                i64::from(if negated { value.wrapping_neg() } else { value })
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Get the radix of `INTEGER_LITERAL` from its spelling.
+    fn get_int_radix(&self) -> u32 {
+        let name = self.get_name().to_ascii_lowercase();
+        let digits = name.trim_end_matches('l');
+
+        if digits.starts_with("0x") {
+            16
+        } else if digits.starts_with("0b") {
+            2
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            8
+        } else {
+            10
+        }
+    }
+
+    /// Get the value of `REAL_LITERAL`, the value of `float` literal is widened.
+    pub fn get_real_value(&self) -> f64 {
+        match self.get_literal() {
+            Some(Literal::REAL(real_value, _)) => *real_value,
+            _ => 0.0,
+        }
+    }
+
+    /// Get the width of `INTEGER_LITERAL` or `REAL_LITERAL` value.
+    pub fn get_number_width(&self) -> NumberWidth {
+        match self.get_literal() {
+            Some(Literal::INTEGER(_, number_width)) | Some(Literal::REAL(_, number_width)) => *number_width,
+            _ => NumberWidth::default(),
+        }
+    }
+
+    /// Get the value of `CHAR_LITERAL`.
+    pub fn get_char_value(&self) -> char {
+        match self.get_literal() {
+            Some(Literal::CHAR(char_value)) => *char_value,
+            _ => Default::default(),
+        }
+    }
+
+    /// Get the value of `BOOLEAN_LITERAL`.
+    pub fn get_bool_value(&self) -> bool {
+        matches!(self.get_literal(), Some(Literal::BOOLEAN(true)))
+    }
+
+    /// Get the decoded value of `STRING_LITERAL`, or the text of `DOC_COMMENT`.
+    pub fn get_str_value(&self) -> &str {
+        match self.get_literal() {
+            Some(Literal::STRING(str_value)) | Some(Literal::DOC_COMMENT(str_value)) => str_value,
+            _ => "",
+        }
+    }
+
+    /// Get the name, which is the spelling of token in source code, except `END_OF_FILE` for the end of file.
+    pub fn get_name(&self) -> &str {
+        match &self.text_ {
+            Some(text) => &text.name_,
+            None => self.symbol_.as_str(),
+        }
+    }
+
+    /// Get the interned name of identifier or reserved word, which is cheap to copy and compare,
+    /// or `None` for the other tokens.
+    pub fn get_symbol(&self) -> Option<Symbol> {
+        match self.text_ {
+            Some(_) => None,
+            None => Some(self.symbol_),
+        }
+    }
+
+    /// Set the whitespace and comments before and after the token.
+    pub fn set_trivia(&mut self, leading_trivia: String, trailing_trivia: String) {
+        self.trivia_ = if leading_trivia.is_empty() && trailing_trivia.is_empty() {
+            None
+        } else {
+            Some(Box::new((leading_trivia, trailing_trivia)))
+        };
     }
 
-    pub fn to_string(&self) -> String {
-        format!("{} Token Type: {} Token Name: {}", self.location_.to_string(),
-                self.token_type_description(), self.name_)
+    /// Get the whitespace and comments before the token, which is empty unless the scanner keeps trivia.
+    pub fn get_leading_trivia(&self) -> &str {
+        self.trivia_.as_ref().map_or("", |trivia| &trivia.0)
+    }
+
+    /// Get the whitespace and comments after the token up to the end of line,
+    /// which is empty unless the scanner keeps trivia.
+    pub fn get_trailing_trivia(&self) -> &str {
+        self.trivia_.as_ref().map_or("", |trivia| &trivia.1)
     }
 
     fn token_type_description(&self) -> String {
-        let buffer = match self.type_ {
+        let buffer = match self.get_token_type() {
             TokenType::INTEGER_LITERAL => "integer",
             TokenType::BOOLEAN_LITERAL => "boolean",
             TokenType::REAL_LITERAL => "real",
@@ -238,6 +956,7 @@ impl Token {
             TokenType::TYPE => "type",
             TokenType::OPERATOR => "operator",
             TokenType::DELIMITER => "delimiter",
+            TokenType::DOC_COMMENT => "doc comment",
             TokenType::END_OF_FILE => "eof",
             TokenType::UNKNOWN => "unknown",
         };
@@ -245,3 +964,131 @@ impl Token {
         buffer.to_string()
     }
 }
+
+impl fmt::Display for Token {
+    /// Print the location, type and name, together with the decoded value of literal.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} Token Type: {} Token Name: {}", self.location_,
+                self.token_type_description(), self.get_name())?;
+
+        match self.get_literal() {
+            Some(Literal::DOC_COMMENT(_)) | None => Ok(()),
+            Some(literal) => write!(f, " Token Value: {}", literal),
+        }
+    }
+}
+
+/// Whether the token at `index` is the operand of unary `-`, such as `2147483648` in `x = -2147483648;`.
+///
+/// The `-` is binary if it follows an operand, such as `a - 1`, `f() - 1` or `a[0] - 1`, except the `)` of a cast to
+/// primitive type, such as `(int) -2147483648`. It is a heuristic on tokens rather than a parse of the expression.
+pub fn is_negated(tokens: &[Token], index: usize) -> bool {
+    let is_minus = index.checked_sub(1).and_then(|minus| tokens.get(minus)).is_some_and(|minus| matches!(minus.get_token_value(), TokenValue::SUB));
+
+    if !is_minus {
+        return false;
+    }
+
+    match index.checked_sub(2).and_then(|previous| tokens.get(previous)) {
+        None => true,
+        Some(_) if is_primitive_cast_end(tokens, index - 2) => true,
+        Some(previous) => {
+            let is_operand = matches!(previous.get_token_type(), TokenType::IDENTIFIER | TokenType::INTEGER_LITERAL | TokenType::REAL_LITERAL
+                        | TokenType::CHAR_LITERAL | TokenType::STRING_LITERAL | TokenType::BOOLEAN_LITERAL)
+                    || matches!(previous.get_token_value(), TokenValue::RPAREN | TokenValue::RBRACK | TokenValue::THIS | TokenValue::LENGTH
+                        | TokenValue::INC | TokenValue::DEC);
+            !is_operand
+        },
+    }
+}
+
+/// Whether the token at `index` is the `)` of a cast to primitive type, such as `(int)`.
+fn is_primitive_cast_end(tokens: &[Token], index: usize) -> bool {
+    let is_type = |token: &Token| matches!(token.get_token_type(), TokenType::TYPE) && token.get_token_value() != TokenValue::STRING;
+
+    index >= 2
+        && matches!(tokens[index].get_token_value(), TokenValue::RPAREN)
+        && is_type(&tokens[index - 1])
+        && matches!(tokens[index - 2].get_token_value(), TokenValue::LPAREN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn scan(source: &str) -> Vec<Token> {
+        Scanner::from_str("<test>", source).filter_map(Result::ok).collect()
+    }
+
+    fn check(source: &str, negated: bool) -> Result<i64, &'static str> {
+        let tokens = scan(source);
+        tokens[0].check_int_literal(negated).map_err(|diagnostic| diagnostic.get_code())
+    }
+
+    #[test]
+    fn tokens_are_compact() {
+        assert_eq!(std::mem::size_of::<TokenLocation>(), 40);
+        assert_eq!(std::mem::size_of::<Token>(), 72);
+    }
+
+    #[test]
+    fn names_and_literals_are_kept() {
+        let tokens = scan("count true 0x1F \"a\\tb\" @");
+        let names: Vec<_> = tokens.iter().map(Token::get_name).collect();
+        assert_eq!(names, ["count", "true", "0x1F", "\"a\\tb\"", "@"]);
+
+        assert_eq!(tokens[0].get_symbol(), Some(Symbol::intern("count")));
+        assert_eq!(tokens[0].get_literal(), None);
+        assert_eq!(tokens[1].get_literal(), Some(&Literal::BOOLEAN(true)));
+        assert_eq!(tokens[2].get_symbol(), None);
+        assert_eq!(tokens[2].get_literal(), Some(&Literal::INTEGER(31, NumberWidth::I32)));
+        assert_eq!(tokens[3].get_str_value(), "a\tb");
+        assert_eq!(tokens[4].get_token_type(), TokenType::UNKNOWN);
+    }
+
+    #[test]
+    fn decimal_literals_exceed_the_maximum_only_when_negated() {
+        assert_eq!(check("2147483647", false), Ok(2147483647));
+        assert_eq!(check("2147483648", true), Ok(-2147483648));
+        assert_eq!(check("2147483648", false), Err(NUMBER_OUT_OF_RANGE));
+        assert_eq!(check("2147483649", true), Err(NUMBER_OUT_OF_RANGE));
+
+        assert_eq!(check("9223372036854775807L", false), Ok(i64::MAX));
+        assert_eq!(check("9223372036854775808L", true), Ok(i64::MIN));
+        assert_eq!(check("9223372036854775808L", false), Err(NUMBER_OUT_OF_RANGE));
+    }
+
+    #[test]
+    fn other_radixes_take_all_bits_as_twos_complement() {
+        assert_eq!(check("0xFFFFFFFF", false), Ok(-1));
+        assert_eq!(check("0xFFFFFFFF", true), Ok(1));
+        assert_eq!(check("0x80000000", false), Ok(-2147483648));
+        assert_eq!(check("037777777777", false), Ok(-1));
+        assert_eq!(check("0b11111111111111111111111111111111", false), Ok(-1));
+        assert_eq!(check("0x100000000", false), Err(NUMBER_OUT_OF_RANGE));
+
+        assert_eq!(check("0xFFFFFFFFFFFFFFFFL", false), Ok(-1));
+        assert_eq!(check("0x7FFFFFFFFFFFFFFFL", false), Ok(i64::MAX));
+    }
+
+    #[test]
+    fn unary_minus_is_told_from_binary_minus() {
+        let cases = [
+            ("-2147483648", 1, true),
+            ("x = -2147483648;", 3, true),
+            ("f(-1, -2)", 3, true),
+            ("a - 1", 2, false),
+            ("f() - 1", 4, false),
+            ("a[0] - 1", 5, false),
+            ("(int) -2147483648", 4, true),
+            ("(a) - 1", 4, false),
+            ("(String) - 1", 4, false),
+            ("a 1", 1, false),
+        ];
+
+        for (source, index, negated) in cases {
+            assert_eq!(is_negated(&scan(source), index), negated, "{}", source);
+        }
+    }
+}